@@ -0,0 +1,34 @@
+// Counts the allocations made while parsing a file full of polygons
+// Compare `cargo bench --bench allocations` with `cargo bench --bench allocations --features smallvec`
+use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}};
+use objld::*;
+use rayon::iter::ParallelIterator;
+
+struct CountingAlloc;
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn main(){
+    let nfaces = 100_000;
+    let input: String = (0..nfaces).map(|i| format!("f {} {} {} {} {} {}\n", i+1, i+2, i+3, i+4, i+5, i+6)).collect();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let t = std::time::Instant::now();
+    let parsed: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+    let elapsed = t.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("Parsed {} hexagons in {}s with {} allocations ({:.2} per face)", nfaces, elapsed.as_secs_f32(), allocations, allocations as f64 / nfaces as f64);
+    assert_eq!(parsed.len(), nfaces + 1);
+}
@@ -0,0 +1,42 @@
+// Compares count_elements and line_count (memchr) with doing the same through str::split, run with `cargo bench --bench counting`
+use objld::*;
+use rayon::{iter::ParallelIterator, str::ParallelString};
+
+fn count_split(input: &str) -> ElementCounts{
+    input.par_split('\n')
+    .map(|line|{
+        let mut counts = ElementCounts::default();
+        match line.trim_start().split([' ', '\t']).next(){
+            Some("v") => counts.positions = 1,
+            Some("vt") => counts.texcoords = 1,
+            Some("vn") => counts.normals = 1,
+            Some("f") => counts.faces = 1,
+            _ => {}
+        }
+        counts
+    })
+    .reduce(ElementCounts::default, |a, b| a + b)
+}
+
+fn main(){
+    let input: String = (0..1_000_000).map(|i| format!("v {:.6} {:.6} {:.6}\nvn 0 0 1\nf {} {} {}\n", i as f32 * 0.37, i as f32 * 1e-3, -(i as f32), i + 1, i + 2, i + 3)).collect();
+    for _ in 0..3{
+        let t = std::time::Instant::now();
+        let split = count_split(&input);
+        let split_time = t.elapsed().as_secs_f32();
+        let t = std::time::Instant::now();
+        let memchr = count_elements(&input);
+        let memchr_time = t.elapsed().as_secs_f32();
+        assert_eq!(split, memchr);
+        println!("count_elements: par_split {}s, memchr {}s ({:.1}x)", split_time, memchr_time, split_time / memchr_time);
+
+        let t = std::time::Instant::now();
+        let split = input.split('\n').count();
+        let split_time = t.elapsed().as_secs_f32();
+        let t = std::time::Instant::now();
+        let memchr = line_count(&input);
+        let memchr_time = t.elapsed().as_secs_f32();
+        assert_eq!(split, memchr);
+        println!("line_count: split {}s, memchr {}s ({:.1}x)", split_time, memchr_time, split_time / memchr_time);
+    }
+}
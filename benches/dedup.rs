@@ -0,0 +1,41 @@
+// Compares dedup_vertices with expand_vertices, how much smaller the vertex buffer gets against how long welding takes,
+// on grids (where most vertices are shared) and on loose triangles (where none are), run with `cargo bench --bench dedup`
+use objld::*;
+use rayon::iter::ParallelIterator;
+
+// n by n quads, every inner position is used by 4 of them (6 triangle corners)
+fn grid(n: usize) -> String{
+    let mut input = String::new();
+    for y in 0..=n{
+        for x in 0..=n { input += &format!("v {} {} 0\nvt {} {}\n", x, y, x as f32 / n as f32, y as f32 / n as f32); }
+    }
+    input += "vn 0 0 1\n";
+    for y in 0..n{
+        for x in 0..n{
+            let i = y * (n + 1) + x + 1;
+            input += &format!("f {0}/{0}/1 {1}/{1}/1 {2}/{2}/1 {3}/{3}/1\n", i, i + 1, i + n + 2, i + n + 1);
+        }
+    }
+    input
+}
+
+// Triangles that don't share anything, so dedup can't do better than expanding
+fn loose(n: usize) -> String{
+    (0..n).map(|i| format!("v {0} 0 0\nv {0} 1 0\nv {0} 0 1\nf {1} {2} {3}\n", i, i * 3 + 1, i * 3 + 2, i * 3 + 3)).collect()
+}
+
+fn main(){
+    for (name, input) in [("grid", grid(500)), ("loose", loose(500_000))]{
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(&input).collect::<Vec<_>>());
+        for _ in 0..3{
+            let t = std::time::Instant::now();
+            let (expanded, _) = expand_vertices(&mesh).expect("Too many vertices for u32 indices!");
+            let expand_time = t.elapsed().as_secs_f32();
+            let t = std::time::Instant::now();
+            let (deduped, _) = dedup_vertices(&mesh, DedupKey::PositionTexNormal).expect("Too many vertices for u32 indices!");
+            let dedup_time = t.elapsed().as_secs_f32();
+            println!("{}: expand {} vertices in {}s, dedup {} vertices in {}s ({:.1}x fewer vertices, {:.1}x the time)",
+                name, expanded.len(), expand_time, deduped.len(), dedup_time, expanded.len() as f32 / deduped.len() as f32, dedup_time / expand_time);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// Times parsing a file of vertices, compare `cargo bench --bench floats` with `cargo bench --bench floats --features fast-float`
+use objld::*;
+use rayon::iter::ParallelIterator;
+
+fn main(){
+    let input: String = (0..1_000_000).map(|i| format!("v {:.6} {:.6} {:.6}\n", i as f32 * 0.37 - 1234.5, i as f32 * 1e-3, -(i as f32))).collect();
+    for _ in 0..3{
+        let t = std::time::Instant::now();
+        let parsed: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+        println!("Parsed {} lines in {}s", parsed.len(), t.elapsed().as_secs_f32());
+    }
+}
@@ -0,0 +1,17 @@
+// Compares parse_positions_only with a full parse_file on a file with the usual mix of lines, run with `cargo bench --bench positions`
+use objld::*;
+use rayon::iter::ParallelIterator;
+
+fn main(){
+    let input: String = (0..250_000).map(|i| format!("v {:.6} {:.6} {:.6}\nvt {:.6} {:.6}\nvn 0 0 1\nf {}/{}/{} {}/{}/{} {}/{}/{} {}/{}/{}\n",
+        i as f32 * 0.37, i as f32 * 1e-3, -(i as f32), (i % 100) as f32 * 0.01, 0.5, i + 1, i + 1, i + 1, i + 2, i + 2, i + 1, i + 3, i + 3, i + 1, i + 4, i + 4, i + 1)).collect();
+    for _ in 0..3{
+        let t = std::time::Instant::now();
+        let parsed: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+        let full = t.elapsed().as_secs_f32();
+        let t = std::time::Instant::now();
+        let positions: Vec<VertexData<f32>> = parse_positions_only(&input).collect();
+        let only = t.elapsed().as_secs_f32();
+        println!("parse_file: {} lines in {}s, parse_positions_only: {} positions in {}s ({:.1}x)", parsed.len(), full, positions.len(), only, full / only);
+    }
+}
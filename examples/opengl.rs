@@ -65,11 +65,8 @@ fn to_raw_data3d(parsed_data: Vec<LineResult<f32, i32>>) -> RawData3D{
                 VertexData::TextureCoord2 { u, v } => {r.vertex_tex.push((u, v))},
                 _ => {},
             },
-            LineResult::FaceLine(f) => match f{
-                Face::Face3 { v1, v2, v3 } => {r.vert_ind.push(v1); r.vert_ind.push(v2); r.vert_ind.push(v3);},
-                Face::Face4 { v1, v2, v3, v4 } => {r.vert_ind.push(v1); r.vert_ind.push(v2); r.vert_ind.push(v3);  r.vert_ind.push(v3); r.vert_ind.push(v4); r.vert_ind.push(v1); },
-            },
-            LineResult::NoData => {},
+            LineResult::FaceLine(f) => r.vert_ind.extend(f.triangles().flatten()), // Fans out from the first vertex, see fan_triangles
+            LineResult::Group(_) | LineResult::Object(_) | LineResult::Material(_) | LineResult::TextureMap(_) | LineResult::FreeFormLine(_) | LineResult::NoData => {},
             LineResult::Error(_e) => {println!("{}", _e)} // Ignore unparsed data
         }
     }
@@ -120,14 +117,14 @@ fn to_opengl_data3d_dedup(raw_data: RawData3D) -> OpenGLData3D{
     o
 }
 
+#[allow(dead_code)] // Kept around to compare against the dedup version
 fn to_opengl_data3d_simple(raw_data: RawData3D) -> OpenGLData3D{
     let mut o = OpenGLData3D::default();
     o.vertex_pos.reserve(raw_data.vert_ind.len());
     o.vertex_tex.reserve(raw_data.vert_ind.len());
     o.vertex_norm.reserve(raw_data.vert_ind.len());
     o.indecies.reserve(raw_data.vert_ind.len());
-    let mut curr_ind = 0;
-    for v in raw_data.vert_ind{
+    for (curr_ind, v) in raw_data.vert_ind.into_iter().enumerate(){
         let coord_ind = v.coord_rindex;
         let tex_ind = v.texcoord_rindex.unwrap();
         let norm_ind = v.normal_rindex.unwrap();
@@ -137,8 +134,7 @@ fn to_opengl_data3d_simple(raw_data: RawData3D) -> OpenGLData3D{
         o.vertex_pos.push(pos_tuple);
         o.vertex_tex.push(tex_tuple);
         o.vertex_norm.push(norm_tuple);
-        o.indecies.push(curr_ind);
-        curr_ind += 1;
+        o.indecies.push(curr_ind as u32);
     }
     o
 }
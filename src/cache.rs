@@ -0,0 +1,107 @@
+use std::io::{self, Read, Write};
+
+use crate::{Face, FaceVertices, Mesh, VertexIndeces};
+
+// Layout (all little endian): MAGIC, then positions, texcoords, normals and tangents as a u64 count followed by the f32s,
+// then a u64 face count and for every face a u32 vertex count followed by its vertices
+// A vertex is a u8 with bit 0 set if it has a tex coord and bit 1 if it has a normal, then the i32 indices it has, coord first
+const MAGIC: &[u8; 8] = b"objld\0\0\x01"; // The last byte is the version
+
+// Writes the mesh in a simple binary format that deserialize_mesh_bin reads back much faster than the .obj can be parsed
+pub fn serialize_mesh_bin<W: Write>(mesh: &Mesh<f32, i32>, w: &mut W) -> io::Result<()>{
+    w.write_all(MAGIC)?;
+    write_floats(w, mesh.positions.len(), mesh.positions.iter().flatten())?;
+    write_floats(w, mesh.texcoords.len(), mesh.texcoords.iter().flatten())?;
+    write_floats(w, mesh.normals.len(), mesh.normals.iter().flatten())?;
+    write_floats(w, mesh.tangents.len(), mesh.tangents.iter().flatten())?;
+    w.write_all(&(mesh.faces.len() as u64).to_le_bytes())?;
+    for face in &mesh.faces{
+        let len = u32::try_from(face.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "face has too many vertices to cache"))?;
+        w.write_all(&len.to_le_bytes())?;
+        for v in face.vertices(){
+            w.write_all(&[u8::from(v.texcoord_rindex.is_some()) | u8::from(v.normal_rindex.is_some()) << 1])?;
+            for i in [Some(v.coord_rindex), v.texcoord_rindex, v.normal_rindex].into_iter().flatten(){
+                w.write_all(&i.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reads a mesh written by serialize_mesh_bin, fails with InvalidData if it isn't one (or is from another version)
+pub fn deserialize_mesh_bin<R: Read>(r: &mut R) -> io::Result<Mesh<f32, i32>>{
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC { return Err(invalid("not an objld mesh cache")); }
+    let mut mesh = Mesh{
+        positions: read_arrays(r)?,
+        texcoords: read_arrays(r)?,
+        normals: read_arrays(r)?,
+        tangents: read_arrays(r)?,
+        faces: Vec::new()
+    };
+    let nfaces = read_u64(r)?;
+    for _ in 0..nfaces{
+        let nverts = u32::from_le_bytes(read_bytes(r)?);
+        if nverts < 3 { return Err(invalid("face with less than 3 vertices")); }
+        let mut verts = FaceVertices::new();
+        for _ in 0..nverts{
+            let [flags] = read_bytes(r)?;
+            let coord_rindex = i32::from_le_bytes(read_bytes(r)?);
+            let mut index = |present: bool| -> io::Result<Option<i32>>{ if present { Ok(Some(i32::from_le_bytes(read_bytes(r)?))) } else { Ok(None) } };
+            verts.push(VertexIndeces{ coord_rindex, texcoord_rindex: index(flags & 1 != 0)?, normal_rindex: index(flags & 2 != 0)? });
+        }
+        mesh.faces.push(match verts.len(){
+            3 => Face::Face3{ v1: verts[0], v2: verts[1], v3: verts[2] },
+            4 => Face::Face4{ v1: verts[0], v2: verts[1], v3: verts[2], v4: verts[3] },
+            _ => Face::FaceN{ verts }
+        });
+    }
+    Ok(mesh)
+}
+
+fn invalid(message: &str) -> io::Error{ io::Error::new(io::ErrorKind::InvalidData, message) }
+
+fn write_floats<'a, W: Write>(w: &mut W, count: usize, mut floats: impl Iterator<Item = &'a f32>) -> io::Result<()>{
+    w.write_all(&(count as u64).to_le_bytes())?;
+    floats.try_for_each(|f| w.write_all(&f.to_le_bytes()))
+}
+
+fn read_bytes<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]>{
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64>{ Ok(u64::from_le_bytes(read_bytes(r)?)) }
+
+fn read_arrays<R: Read, const N: usize>(r: &mut R) -> io::Result<Vec<[f32; N]>>{
+    let count = read_u64(r)?;
+    // Not trusting the count for the allocation, a corrupt file would just run out of data instead of memory
+    let mut arrays = Vec::with_capacity(count.min(1 << 16) as usize);
+    for _ in 0..count{
+        let mut array = [0.0; N];
+        for f in &mut array { *f = f32::from_le_bytes(read_bytes(r)?); }
+        arrays.push(array);
+    }
+    Ok(arrays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collect_mesh, generate_tangents, parse_file};
+    use rayon::iter::ParallelIterator;
+
+    #[test]
+    fn test_mesh_bin_roundtrip(){
+        let input = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0.5 1.5 -2\nvt 0 0\nvt 1 0\nvt 1 1\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1\nf 1//1 3//1 4//1 5//1\nf 1 2 3 4 5\nf -1/-1 -2/-2 -3/-3\n";
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        generate_tangents(&mut mesh);
+        let mut bytes = Vec::new();
+        serialize_mesh_bin(&mesh, &mut bytes).unwrap();
+        assert_eq!(deserialize_mesh_bin(&mut bytes.as_slice()).unwrap(), mesh);
+        assert_eq!(deserialize_mesh_bin(&mut &bytes[..bytes.len() - 1]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(deserialize_mesh_bin(&mut &b"not a mesh cache"[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
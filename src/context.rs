@@ -0,0 +1,124 @@
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
+
+use rayon::iter::ParallelIterator;
+
+use crate::{Face, LineResult, MaterialRef, ObjFloat, parse_file};
+
+// A face along with the group, object and material that were active when it appeared
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContextualFace<'a, I>{
+    pub face: Face<I>,
+    pub group: Option<Cow<'a, str>>,
+    pub object: Option<Cow<'a, str>>,
+    pub material: Option<Cow<'a, str>>
+}
+
+// Keeps track of g/o/usemtl lines while going through the results in order, and attaches them to every face
+pub fn contextual_faces<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>) -> Vec<ContextualFace<'a, I>>{
+    let (mut group, mut object, mut material) = (None, None, None);
+    let mut faces = Vec::new();
+    for line in results{
+        match line{
+            LineResult::Group(name) => group = Some(name),
+            LineResult::Object(name) => object = Some(name),
+            LineResult::Material(MaterialRef::UseMtl(name)) => material = name,
+            LineResult::FaceLine(face) => faces.push(ContextualFace{ face, group: group.clone(), object: object.clone(), material: material.clone() }),
+            _ => {}
+        }
+    }
+    faces
+}
+
+// Parses the input and puts every face under the name of the last g or o line before it, for importing a file as separate parts
+// Faces before any g or o line end up under an empty name
+pub fn faces_by_group<T, I>(input: &str) -> HashMap<String, Vec<Face<I>>>
+where T: Send + FromStr + ObjFloat, I: Send + FromStr{
+    let mut groups: HashMap<String, Vec<Face<I>>> = HashMap::new();
+    let mut name = Cow::Borrowed("");
+    for line in parse_file::<T, I>(input).collect::<Vec<_>>(){
+        match line{
+            LineResult::Group(n) | LineResult::Object(n) => name = n,
+            LineResult::FaceLine(face) => match groups.get_mut(name.as_ref()){
+                Some(faces) => faces.push(face),
+                None => { groups.insert(name.to_string(), vec![face]); }
+            },
+            _ => {}
+        }
+    }
+    groups
+}
+
+// The names of the materials the faces use (in order of first use) and, for every face, the index of its material in that list
+// Faces with no material (before any usemtl, or after an empty one) get an entry with an empty name
+pub fn material_table<T, I>(results: &[LineResult<'_, T, I>]) -> (Vec<String>, Vec<usize>){
+    let mut names: Vec<String> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let mut material = "";
+    let mut faces = Vec::new();
+    for line in results{
+        match line{
+            LineResult::Material(MaterialRef::UseMtl(name)) => material = name.as_deref().unwrap_or(""),
+            LineResult::FaceLine(_) => faces.push(*indices.entry(material).or_insert_with(||{
+                names.push(material.to_string());
+                names.len() - 1
+            })),
+            _ => {}
+        }
+    }
+    (names, faces)
+}
+
+// Makes faces with the same material contiguous (materials ordered by first appearance), faces keep their relative order within a material
+pub fn sort_faces_by_material<'a, I>(faces: &mut Vec<ContextualFace<'a, I>>){
+    let keys: Vec<usize> = {
+        let mut order: HashMap<Option<&str>, usize> = HashMap::new();
+        faces.iter().map(|f|{
+            let next = order.len();
+            *order.entry(f.material.as_deref()).or_insert(next)
+        }).collect()
+    };
+    let mut keyed: Vec<(usize, ContextualFace<'a, I>)> = keys.into_iter().zip(faces.drain(..)).collect();
+    keyed.sort_by_key(|(k, _)| *k); // Stable
+    faces.extend(keyed.into_iter().map(|(_, f)| f));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_material(){
+        let input = "v 0 0 0\nusemtl red\nf 1 1 1\nusemtl blue\nf 2 2 2\nusemtl red\nf 3 3 3\nusemtl blue\nf 4 4 4\n";
+        let mut faces = contextual_faces(parse_file::<f32, u32>(input).collect::<Vec<_>>());
+        sort_faces_by_material(&mut faces);
+        let order: Vec<(u32, &str)> = faces.iter().map(|f| (f.face.vertices().next().unwrap().coord_rindex, f.material.as_deref().unwrap())).collect();
+        assert_eq!(order, vec![(1, "red"), (3, "red"), (2, "blue"), (4, "blue")]);
+    }
+
+    #[test]
+    fn test_usemtl_reset(){
+        let input = "v 0 0 0\nusemtl red\nf 1 1 1\nusemtl (null)\nf 1 1 1\n";
+        let faces = contextual_faces(parse_file::<f32, u32>(input).collect::<Vec<_>>());
+        assert_eq!(faces.iter().map(|f| f.material.as_deref()).collect::<Vec<_>>(), vec![Some("red"), None]);
+    }
+
+    #[test]
+    fn test_material_table(){
+        let input = "v 0 0 0\nusemtl red\nf 1 1 1\nusemtl blue\nf 1 1 1\nf 1 1 1\nusemtl red\nf 1 1 1\n";
+        let results: Vec<LineResult<f32, u32>> = parse_file(input).collect();
+        assert_eq!(material_table(&results), (vec![String::from("red"), String::from("blue")], vec![0, 1, 1, 0]));
+        let results: Vec<LineResult<f32, u32>> = parse_file("f 1 1 1\nusemtl red\nf 1 1 1\n").collect();
+        assert_eq!(material_table(&results), (vec![String::new(), String::from("red")], vec![0, 1]));
+    }
+
+    #[test]
+    fn test_faces_by_group(){
+        let input = "v 0 0 0\nf 1 1 1\ng wheel\nf 1 1 1\nf 2 2 2\no body\nf 3 3 3\ng wheel\nf 4 4 4\n";
+        let groups = faces_by_group::<f32, u32>(input);
+        let firsts = |name: &str| groups[name].iter().map(|f| f.vertices().next().unwrap().coord_rindex).collect::<Vec<_>>();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(firsts(""), vec![1]);
+        assert_eq!(firsts("wheel"), vec![1, 2, 4]);
+        assert_eq!(firsts("body"), vec![3]);
+    }
+}
@@ -0,0 +1,59 @@
+// Conversions into the vector types of the usual math crates, behind the glam and nalgebra features
+// Missing components are 0 and the w of a Coord4 is dropped, like Mesh does (see VertexData::homogenize for using it)
+
+use crate::VertexData;
+
+// x, y, z (or u, v, w for tex coords)
+fn xyz(v: VertexData<f32>) -> [f32; 3]{
+    match v{
+        VertexData::Coord2{ x, y } => [x, y, 0.0],
+        VertexData::Coord3{ x, y, z } | VertexData::Coord4{ x, y, z, w: _ } | VertexData::Normal{ x, y, z } => [x, y, z],
+        VertexData::TextureCoord3{ u, v, w } => [u, v, w],
+        VertexData::TextureCoord2{ u, v } => [u, v, 0.0],
+        VertexData::TextureCoord1{ u } => [u, 0.0, 0.0],
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<VertexData<f32>> for glam::Vec3{
+    fn from(v: VertexData<f32>) -> Self{ glam::Vec3::from_array(xyz(v)) }
+}
+
+#[cfg(feature = "glam")]
+impl From<VertexData<f32>> for glam::Vec2{
+    fn from(v: VertexData<f32>) -> Self{ glam::Vec3::from(v).truncate() }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<VertexData<f32>> for nalgebra::Vector3<f32>{
+    fn from(v: VertexData<f32>) -> Self{ nalgebra::Vector3::from(xyz(v)) }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<VertexData<f32>> for nalgebra::Vector2<f32>{
+    fn from(v: VertexData<f32>) -> Self{
+        let [x, y, _] = xyz(v);
+        nalgebra::Vector2::new(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam(){
+        assert_eq!(glam::Vec3::from(VertexData::Coord3{ x: 1.0, y: 2.0, z: 3.0 }), glam::Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(glam::Vec3::from(VertexData::Coord2{ x: 1.0, y: 2.0 }), glam::Vec3::new(1.0, 2.0, 0.0));
+        assert_eq!(glam::Vec2::from(VertexData::TextureCoord2{ u: 0.25, v: 0.5 }), glam::Vec2::new(0.25, 0.5));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn test_nalgebra(){
+        assert_eq!(nalgebra::Vector3::from(VertexData::Coord3{ x: 1.0, y: 2.0, z: 3.0 }), nalgebra::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(nalgebra::Vector3::from(VertexData::Normal{ x: 0.0, y: -1.0, z: 0.0 }), nalgebra::Vector3::new(0.0, -1.0, 0.0));
+        assert_eq!(nalgebra::Vector2::from(VertexData::TextureCoord1{ u: 0.25 }), nalgebra::Vector2::new(0.25, 0.0));
+    }
+}
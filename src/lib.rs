@@ -1,17 +1,36 @@
-use std::str::FromStr;
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU32, ops::Range, str::FromStr, sync::atomic::{AtomicBool, Ordering}};
 
-use nom::{IResult, branch::alt, bytes::complete::tag, character::complete::{digit0, digit1, one_of, space0, space1}, combinator::{eof, opt, rest}, sequence::tuple, multi::many0};
+use nom::{IResult, branch::alt, bytes::complete::{is_not, tag}, character::complete::{digit0, digit1, one_of, space0, space1}, combinator::{eof, opt, peek, rest, verify}, sequence::{delimited, preceded, terminated, tuple}, multi::{fold_many1, many0, many_m_n, separated_list1}};
 use nom::combinator::{map, recognize};
 use nom::character::complete::char;
-use rayon::{iter::ParallelIterator, str::ParallelString};
+use rayon::{iter::{Either, IndexedParallelIterator, IntoParallelIterator, ParallelIterator}, str::ParallelString};
 
+mod mesh;
+pub use mesh::*;
+mod context;
+pub use context::*;
+mod sink;
+pub use sink::*;
+mod relative;
+pub use relative::*;
+mod reader;
+pub use reader::*;
+mod writer;
+pub use writer::*;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+mod convert;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+pub use cache::*;
+pub mod mtl;
 
-#[derive(Debug, PartialEq)]
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum VertexData<T>{
     Coord2{ x: T, y: T }, // Unofficial 
     Coord3{ x: T, y: T, z: T },
-
-    // No support for w in Coords
+    Coord4{ x: T, y: T, z: T, w: T }, // Rational (homogeneous) coords, see homogenize
 
     Normal{x: T, y: T, z: T},
 
@@ -20,6 +39,30 @@ pub enum VertexData<T>{
     TextureCoord1{ u: T }
 }
 
+impl<T> VertexData<T>
+where T: Copy + PartialEq + Default + std::ops::Div<Output = T>{
+    // The 3d position, with x, y and z divided by w for Coord4, None for anything that isn't a 3d/4d coord or if w is 0
+    pub fn homogenize(&self) -> Option<[T; 3]>{
+        match *self{
+            VertexData::Coord3{ x, y, z } => Some([x, y, z]),
+            VertexData::Coord4{ x, y, z, w } if w != T::default() => Some([x / w, y / w, z / w]),
+            _ => None
+        }
+    }
+}
+
+// Appends the components of the vertex data (x, y, z or u, v, w, however many it has) to a flat buffer, useful for building VBOs
+pub fn push_xyz<T: Into<f32> + Copy>(v: &VertexData<T>, out: &mut Vec<f32>){
+    match *v{
+        VertexData::Coord2{ x, y } => out.extend([x.into(), y.into()]),
+        VertexData::Coord3{ x, y, z } | VertexData::Normal{ x, y, z } => out.extend([x.into(), y.into(), z.into()]),
+        VertexData::Coord4{ x, y, z, w } => out.extend([x.into(), y.into(), z.into(), w.into()]),
+        VertexData::TextureCoord3{ u, v, w } => out.extend([u.into(), v.into(), w.into()]),
+        VertexData::TextureCoord2{ u, v } => out.extend([u.into(), v.into()]),
+        VertexData::TextureCoord1{ u } => out.push(u.into()),
+    }
+}
+
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct VertexIndeces<I>{
@@ -28,7 +71,107 @@ pub struct VertexIndeces<I>{
     pub normal_rindex: Option<I>,
 }
 
-#[derive(Debug, PartialEq)]
+impl<I> VertexIndeces<I>{
+    // Applies f to every present index, like for converting to another index type
+    pub fn map<J>(self, f: impl Fn(I) -> J) -> VertexIndeces<J>{
+        VertexIndeces{
+            coord_rindex: f(self.coord_rindex),
+            texcoord_rindex: self.texcoord_rindex.map(&f),
+            normal_rindex: self.normal_rindex.map(&f),
+        }
+    }
+}
+
+impl<I> VertexIndeces<I>
+where I: Copy{
+    // Fills in missing texcoord/normal indices, so every vertex of a mesh can be handled the same way
+    pub fn with_defaults(&self, default_tex: I, default_normal: I) -> VertexIndeces<I>{
+        VertexIndeces{
+            coord_rindex: self.coord_rindex,
+            texcoord_rindex: Some(self.texcoord_rindex.unwrap_or(default_tex)),
+            normal_rindex: Some(self.normal_rindex.unwrap_or(default_normal)),
+        }
+    }
+}
+
+impl<I> VertexIndeces<I>
+where I: Copy + TryInto<i64>{
+    /// Resolves every index of this vertex into a 0-based index, given the number of coords, texcoords and normals it can refer to
+    /// Returns None if any present index is out of range
+    pub fn resolve(&self, ncoords: usize, ntexcoords: usize, nnormals: usize) -> Option<VertexIndeces<usize>>{
+        self.resolve_with_base(ncoords, ntexcoords, nnormals, 1)
+    }
+
+    /// Same as resolve, for files whose first element is index_base instead of 1 (see resolve_index_with_base)
+    pub fn resolve_with_base(&self, ncoords: usize, ntexcoords: usize, nnormals: usize, index_base: u32) -> Option<VertexIndeces<usize>>{
+        let resolve = |i, len| resolve_index_with_base(i, len, index_base);
+        Some(VertexIndeces{
+            coord_rindex: resolve(self.coord_rindex, ncoords)?,
+            texcoord_rindex: match self.texcoord_rindex { Some(i) => Some(resolve(i, ntexcoords)?), None => None },
+            normal_rindex: match self.normal_rindex { Some(i) => Some(resolve(i, nnormals)?), None => None },
+        })
+    }
+}
+
+// VertexIndeces with positive (absolute) u32 indices, which are never 0 so Option<CompactVertexIndeces> takes no extra space
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct CompactVertexIndeces{
+    pub coord_rindex: NonZeroU32,
+    pub texcoord_rindex: Option<NonZeroU32>,
+    pub normal_rindex: Option<NonZeroU32>,
+}
+
+impl<I> VertexIndeces<I>
+where I: Copy + TryInto<u32>{
+    // Returns None if any present index is 0, negative (see resolve_relative) or too large for a u32
+    pub fn compact(&self) -> Option<CompactVertexIndeces>{
+        let compact = |i: I| NonZeroU32::new(i.try_into().ok()?);
+        Some(CompactVertexIndeces{
+            coord_rindex: compact(self.coord_rindex)?,
+            texcoord_rindex: match self.texcoord_rindex { Some(i) => Some(compact(i)?), None => None },
+            normal_rindex: match self.normal_rindex { Some(i) => Some(compact(i)?), None => None },
+        })
+    }
+}
+
+impl From<CompactVertexIndeces> for VertexIndeces<u32>{
+    fn from(v: CompactVertexIndeces) -> Self {
+        VertexIndeces{
+            coord_rindex: v.coord_rindex.get(),
+            texcoord_rindex: v.texcoord_rindex.map(NonZeroU32::get),
+            normal_rindex: v.normal_rindex.map(NonZeroU32::get),
+        }
+    }
+}
+
+/// Resolves an index as found in a .obj file (1-based, or negative to count back from the end) into a 0-based index into `len` elements
+/// Returns None if the index is 0, out of range or doesn't fit in an i64
+pub fn resolve_index<I>(rindex: I, len: usize) -> Option<usize>
+where I: TryInto<i64>{
+    resolve_index_with_base(rindex, len, 1)
+}
+
+/// Same as resolve_index, but positive indices start at index_base, for the few tools that write 0-based files
+/// Negative indices still count back from the end, -1 is the last element whatever the base
+/// Note: Every other place that turns a file index into a position in a list goes through this, the arithmetic is all checked
+pub fn resolve_index_with_base<I>(rindex: I, len: usize, index_base: u32) -> Option<usize>
+where I: TryInto<i64>{
+    let rindex: i64 = rindex.try_into().ok()?;
+    let index = if rindex < 0 {
+        len.checked_sub(usize::try_from(rindex.unsigned_abs()).ok()?)?
+    }else{
+        usize::try_from(rindex).ok()?.checked_sub(usize::try_from(index_base).ok()?)?
+    };
+    (index < len).then_some(index)
+}
+
+// Storage for the vertices of polygons with more than 4 vertices, inline up to 6 vertices with the smallvec feature
+#[cfg(feature = "smallvec")]
+pub type FaceVertices<I> = smallvec::SmallVec<[VertexIndeces<I>; 6]>;
+#[cfg(not(feature = "smallvec"))]
+pub type FaceVertices<I> = Vec<VertexIndeces<I>>;
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Face<I>{
     Face3{
         v1: VertexIndeces<I>,
@@ -41,9 +184,120 @@ pub enum Face<I>{
         v2: VertexIndeces<I>,
         v3: VertexIndeces<I>,
         v4: VertexIndeces<I>
+    },
+
+    FaceN{
+        verts: FaceVertices<I>
     }
 }
 
+impl<I> Face<I>{
+    pub fn len(&self) -> usize{
+        match self{
+            Face::Face3{ .. } => 3,
+            Face::Face4{ .. } => 4,
+            Face::FaceN{ verts } => verts.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{ self.len() == 0 }
+
+    pub fn vertex(&self, n: usize) -> Option<&VertexIndeces<I>>{
+        match self{
+            Face::Face3{ v1, v2, v3 } => [v1, v2, v3].get(n).copied(),
+            Face::Face4{ v1, v2, v3, v4 } => [v1, v2, v3, v4].get(n).copied(),
+            Face::FaceN{ verts } => verts.get(n),
+        }
+    }
+
+    // In winding order
+    pub fn vertices(&self) -> impl Iterator<Item = &VertexIndeces<I>>{
+        (0..self.len()).filter_map(move |n| self.vertex(n))
+    }
+
+    pub fn vertices_mut(&mut self) -> impl Iterator<Item = &mut VertexIndeces<I>>{
+        match self{
+            Face::Face3{ v1, v2, v3 } => Either::Left([v1, v2, v3].into_iter().chain(None)),
+            Face::Face4{ v1, v2, v3, v4 } => Either::Left([v1, v2, v3].into_iter().chain(Some(v4))),
+            Face::FaceN{ verts } => Either::Right(verts.iter_mut()),
+        }
+    }
+
+    // Turns the face around by reversing the order of its vertices, the first vertex stays first (so f 1 2 3 4 becomes f 1 4 3 2)
+    pub fn reverse_winding(&mut self){
+        match self{
+            Face::Face3{ v2, v3, .. } => std::mem::swap(v2, v3),
+            Face::Face4{ v2, v4, .. } => std::mem::swap(v2, v4),
+            Face::FaceN{ verts } => if let Some(rest) = verts.get_mut(1..) { rest.reverse() },
+        }
+    }
+
+    // True if any coord index appears more than once (like f 1 1 2)
+    // Note: Compares the indices as written, so a positive and a negative index referring to the same position aren't caught
+    pub fn is_degenerate(&self) -> bool
+    where I: PartialEq{
+        self.vertices().enumerate().any(|(n, v)| self.vertices().skip(n + 1).any(|other| other.coord_rindex == v.coord_rindex))
+    }
+
+    // Applies f to every index of every vertex (see VertexIndeces::map), keeping the variant
+    pub fn map_indices<J>(self, f: impl Fn(I) -> J) -> Face<J>{
+        self.map_vertices(|v| v.map(&f))
+    }
+
+    // Applies f to every vertex, keeping the variant
+    pub fn map_vertices<J>(self, f: impl Fn(VertexIndeces<I>) -> VertexIndeces<J>) -> Face<J>{
+        match self{
+            Face::Face3{ v1, v2, v3 } => Face::Face3{ v1: f(v1), v2: f(v2), v3: f(v3) },
+            Face::Face4{ v1, v2, v3, v4 } => Face::Face4{ v1: f(v1), v2: f(v2), v3: f(v3), v4: f(v4) },
+            Face::FaceN{ verts } => Face::FaceN{ verts: verts.into_iter().map(f).collect() },
+        }
+    }
+
+    // Splits the face into triangles fanning out from the first vertex (see fan_triangles)
+    pub fn triangles(&self) -> impl Iterator<Item = [VertexIndeces<I>; 3]> + '_
+    where I: Copy{
+        fan_triangles(self.len()).filter_map(move |[a, b, c]| Some([*self.vertex(a)?, *self.vertex(b)?, *self.vertex(c)?]))
+    }
+
+    // Same as triangles, but with a policy for faces that have tex coords or normals on only some of their vertices
+    // Returns None if the policy is Reject and the face has such an attribute
+    pub fn triangles_with_policy(&self, policy: PartialAttributes) -> Option<impl Iterator<Item = [VertexIndeces<I>; 3]> + '_>
+    where I: Copy{
+        let partial = |present: usize| present != 0 && present != self.len();
+        let partial_tex = partial(self.vertices().filter(|v| v.texcoord_rindex.is_some()).count());
+        let partial_normal = partial(self.vertices().filter(|v| v.normal_rindex.is_some()).count());
+        let (drop_tex, drop_normal) = match policy{
+            PartialAttributes::Keep => (false, false),
+            PartialAttributes::Drop => (partial_tex, partial_normal),
+            PartialAttributes::Reject if partial_tex || partial_normal => return None,
+            PartialAttributes::Reject => (false, false),
+        };
+        Some(self.triangles().map(move |tri| tri.map(|mut v|{
+            if drop_tex { v.texcoord_rindex = None; }
+            if drop_normal { v.normal_rindex = None; }
+            v
+        })))
+    }
+}
+
+// What Face::triangles_with_policy does with a tex coord or normal that only some vertices of a face have
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum PartialAttributes{
+    #[default]
+    Keep, // Leave the indices as they are, so some triangle vertices have the attribute and some don't (like Face::triangles)
+    Drop, // Remove the attribute from every vertex of the face, so its triangles are uniform
+    Reject // Don't triangulate the face at all
+}
+
+// Splits a polygon with n vertices into triangles (as positions in the polygon) fanning out from the first vertex: 0 1 2, 0 2 3, ...
+// This keeps the winding of the polygon, and for a quad gives 0 1 2, 0 2 3 so the triangles share the 0-2 diagonal
+pub fn fan_triangles(n: usize) -> FanTriangles{
+    (1..n.saturating_sub(1)).map(|k| [0, k, k + 1])
+}
+
+// Named so the fan of a face can be kept around while it's being walked, see mesh::Triangles
+pub type FanTriangles = std::iter::Map<Range<usize>, fn(usize) -> [usize; 3]>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,13 +309,13 @@ mod tests {
 
     #[test]
     fn test_float2() {
-        let (_, res) : (_, f32) = parse_float("2, hj!").unwrap();
+        let (_, res) : (_, f32) = parse_float("2, hj!", &ParseOptions::default()).unwrap();
         assert_eq!(res, 2.0);
     }
 
     #[test]
     fn test_vert(){
-        let (unconsumed, res) : (_, VertexData<f32>) = parse_coord2(" v 1.0 -2.0 # hi!").unwrap();
+        let (unconsumed, res) : (_, VertexData<f32>) = parse_coord2(" v 1.0 -2.0 # hi!", &ParseOptions::default()).unwrap();
         assert_eq!(res, VertexData::Coord2{x: 1.0, y: -2.0});
         assert_eq!(unconsumed, " # hi!");
     }
@@ -84,9 +338,78 @@ mod tests {
 
     #[test]
     fn test_normal(){
-        let res : IResult<_, VertexData<f32>> = parse_normal(" vn 1.0 -2.0 # hi!");
+        let res : IResult<_, VertexData<f32>> = parse_normal(" vn 1.0 -2.0 # hi!", &ParseOptions::default());
         assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("# hi!", nom::error::ErrorKind::Digit)))) 
     }
+    #[test]
+    fn test_float_unrepresentable(){
+        let options = ParseOptions::default();
+        let res : IResult<_, f32> = parse_float("1e400", &options);
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("1e400", nom::error::ErrorKind::TooLarge))));
+        let res : IResult<_, f64> = parse_float("-1E+400 ", &options);
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("-1E+400", nom::error::ErrorKind::TooLarge))));
+        let (_, res) : (_, f32) = parse_float("1e38", &options).unwrap();
+        assert_eq!(res, 1e38);
+        let (_, res) : (_, f64) = parse_float("1e300", &options).unwrap();
+        assert_eq!(res, 1e300);
+
+        // A narrower type than f32 overflows without an exponent, like a half float does past 65504
+        #[derive(Debug, PartialEq)]
+        struct Half(f32);
+        impl FromStr for Half{
+            type Err = std::num::ParseFloatError;
+            fn from_str(s: &str) -> Result<Self, Self::Err>{ s.parse().map(|f: f32| Half(if f.abs() > 65504.0 { f32::INFINITY.copysign(f) } else { f })) }
+        }
+        impl ObjFloat for Half{
+            fn from_num(num: &str) -> Option<Self>{ num.parse().ok() }
+            fn is_infinite(&self) -> bool{ self.0.is_infinite() }
+            fn clamp_unit(self) -> Self{ Half(self.0.clamp_unit()) }
+            fn repeat_unit(self) -> Self{ Half(self.0.repeat_unit()) }
+        }
+        let res : IResult<_, Half> = parse_float("70000", &options);
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("70000", nom::error::ErrorKind::TooLarge))));
+        assert_eq!(parse_float::<Half>("60000", &options), Ok(("", Half(60000.0))));
+    }
+
+    #[cfg(feature = "fast-float")]
+    #[test]
+    fn test_float_fast(){
+        for num in ["0", "-0.0", "1", "+2.5", "3.14159265358979", "-1234.1", "1e-7", "6.02214076e23", "3.4028235e38", "1.17549435E-38", "0.1", "16777217"]{
+            let (_, fast) : (_, f32) = parse_float_fast(num).unwrap();
+            assert_eq!(fast.to_bits(), num.parse::<f32>().unwrap().to_bits());
+            let (_, fast) : (_, f64) = parse_float_fast(num).unwrap();
+            assert_eq!(fast.to_bits(), num.parse::<f64>().unwrap().to_bits());
+            let (_, default) : (_, f32) = parse_float(num, &ParseOptions::default()).unwrap();
+            assert_eq!(default.to_bits(), num.parse::<f32>().unwrap().to_bits());
+        }
+    }
+
+    #[test]
+    fn test_num_unrepresentable(){
+        let res : IResult<_, u8> = parse_num("300");
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("300", nom::error::ErrorKind::TooLarge))));
+        let res : IResult<_, u32> = parse_num("-1");
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("-1", nom::error::ErrorKind::Satisfy))));
+        let res : IResult<_, i8> = parse_num("-300"); // Signed, so just too large
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("-300", nom::error::ErrorKind::TooLarge))));
+        let res : IResult<_, u32> = parse_num("1.5");
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("1.5", nom::error::ErrorKind::Digit))));
+
+        let res: Vec<LineResult<f32, u32>> = parse_file("f 1 2 -1\nf 1 2 99999999999\n").collect();
+        assert!(matches!(&res[0], LineResult::Error(e) if e == "negative index not allowed with an unsigned index type, found '-1'"));
+        assert!(matches!(&res[1], LineResult::Error(e) if e == "number too large, found '99999999999'"));
+    }
+
+    #[test]
+    fn test_negative_zero_index(){
+        let res : IResult<_, i32> = parse_index("-0 1 2");
+        assert_eq!(res, Err(nom::Err::Error(nom::error::Error::new("-0", nom::error::ErrorKind::Verify))));
+        assert!(parse_line::<f32, i32>("f -0 1 2").is_err());
+        assert!(parse_line::<f32, i32>("f 1/-00/2 1 2").is_err());
+        let (_, res) : (_, i32) = parse_index("-10").unwrap();
+        assert_eq!(res, -10);
+    }
+
     #[test]
     fn test_num1(){
         let (_, res) : (_, i32) = parse_num("1").unwrap();
@@ -103,56 +426,1519 @@ mod tests {
             assert_eq!(v3, VertexIndeces::<u32>{coord_rindex: 2.try_into().unwrap(), texcoord_rindex: Some(1.try_into().unwrap()), normal_rindex: None});
         }else{ panic!("Wrong line type!"); }
     }
+
+    #[test]
+    fn test_face_no_slashes(){
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("f 1 2/5 3//4").unwrap();
+        if let LineResult::FaceLine(Face::Face3{v1, v2, v3}) = res{
+            assert_eq!(v1, VertexIndeces{coord_rindex: 1, texcoord_rindex: None, normal_rindex: None});
+            assert_eq!(v2, VertexIndeces{coord_rindex: 2, texcoord_rindex: Some(5), normal_rindex: None});
+            assert_eq!(v3, VertexIndeces{coord_rindex: 3, texcoord_rindex: None, normal_rindex: Some(4)});
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_face_u64(){
+        let (_, res) : (_, LineResult<f32, u64>) = parse_line("f 5000000000 5000000001 5000000002").unwrap();
+        if let LineResult::FaceLine(Face::Face3{v1, v2, v3}) = res{
+            assert_eq!(v1.coord_rindex, 5_000_000_000);
+            assert_eq!(v2.coord_rindex, 5_000_000_001);
+            assert_eq!(v3.coord_rindex, 5_000_000_002);
+            assert_eq!(v3.resolve(6_000_000_000, 0, 0).unwrap().coord_rindex, 5_000_000_001);
+            assert_eq!(v3.resolve(5_000_000_001, 0, 0), None);
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_index_base(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 0 1/0 2//2").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        let resolved: Vec<VertexIndeces<usize>> = face.vertices().map(|v| v.resolve_with_base(3, 1, 3, 0).unwrap()).collect();
+        assert_eq!(resolved.iter().map(|v| v.coord_rindex).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!((resolved[1].texcoord_rindex, resolved[2].normal_rindex), (Some(0), Some(2)));
+        assert_eq!(face.vertex(0).unwrap().resolve(3, 1, 3), None); // 0 is never valid with the usual base
+        assert_eq!(resolve_index_with_base(3, 3, 0), None);
+        assert_eq!(resolve_index_with_base(-1, 3, 0), Some(2));
+
+        // With the option the indices are made 1-based while parsing, so the Mesh functions get them right
+        let options = ParseOptions{ index_base: 0, ..Default::default() };
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 0//0 1//0 2//0\nf -3 -2 -1\nf 1 2 3\n";
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file_with_options(input, options.clone()).collect::<Vec<_>>());
+        let triangles: Vec<_> = mesh.triangles().collect();
+        assert_eq!(triangles.len(), 2); // 3 is out of range
+        assert_eq!(triangles[0].map(|v| v.position), [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(triangles[0][0].normal, Some([0.0, 0.0, 1.0]));
+        assert_eq!(triangles[1], triangles[0].map(|v| ResolvedVertex{ normal: None, ..v }));
+        assert!(parse_line_with_options::<f32, u8>("f 0 1 255", &options).is_err()); // 256 once shifted
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("f 0/+1 1 2", &options).unwrap();
+        assert!(matches!(res, LineResult::FaceLine(Face::Face3{ v1, .. }) if v1.coord_rindex == 1 && v1.texcoord_rindex == Some(2)));
+    }
+
+    #[test]
+    fn test_resolve_i64(){
+        let (_, res) : (_, i64) = parse_num("-5000000000").unwrap();
+        assert_eq!(res, -5_000_000_000);
+        assert_eq!(resolve_index(res, 6_000_000_000), Some(1_000_000_000));
+        assert_eq!(resolve_index(-1i64, 6_000_000_000), Some(5_999_999_999));
+        assert_eq!(resolve_index(res, 4_000_000_000), None);
+        assert_eq!(resolve_index(0i64, 10), None);
+        assert_eq!(resolve_index(u64::MAX, 10), None);
+    }
+
+    #[test]
+    fn test_resolve_index_bounds(){
+        assert_eq!(resolve_index(-5i32, 5), Some(0)); // -len is the first element
+        assert_eq!(resolve_index(-6i32, 5), None);
+        assert_eq!(resolve_index(-1i32, 0), None);
+        assert_eq!(resolve_index(5u32, 5), Some(4));
+        assert_eq!(resolve_index(6u32, 5), None);
+        assert_eq!(resolve_index(i64::MIN, usize::MAX), Some(usize::MAX - (1 << 63)));
+        assert_eq!(resolve_index(i64::MIN, 1 << 63), Some(0));
+        assert_eq!(resolve_index(i64::MIN, (1 << 63) - 1), None);
+        assert_eq!(resolve_index(-1i64, usize::MAX), Some(usize::MAX - 1));
+        assert_eq!(resolve_index(i64::MAX, usize::MAX), Some(i64::MAX as usize - 1));
+        assert_eq!(resolve_index_with_base(i64::MAX, usize::MAX, 0), Some(i64::MAX as usize));
+        assert_eq!(resolve_index_with_base(0i64, usize::MAX, u32::MAX), None);
+    }
+
+    #[test]
+    fn test_parse_strict(){
+        let clean = parse_strict::<f32, u32>("v 1 2 3\nv 4 5 6\nf 1 2 1\n").unwrap();
+        assert_eq!(clean.len(), 4);
+        let errors = parse_strict::<f32, u32>("v 1 2 3\nv oops\nf 1 2 1\nf 1\n").unwrap_err();
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(errors[1].message, "a face needs at least 3 vertices");
+    }
+
+    #[test]
+    fn test_parse_errors(){
+        let input = "v 0 0 x\nv 0 0 0\n".repeat(5_000) + "f 1\n";
+        let errors = parse_errors::<f32, u32>(&input);
+        assert_eq!(errors.len(), 5_001);
+        assert!(errors.windows(2).all(|w| w[0].line < w[1].line));
+        assert_eq!((errors[0].line, errors[1].line), (1, 3));
+        assert_eq!(errors.last().unwrap().to_string(), "line 10001: a face needs at least 3 vertices");
+    }
+
+    #[test]
+    fn test_error_histogram(){
+        let input = "v 0 0 0\nv 1 x 0\nv 1 0 y\nf 1\nf 1 2\nvn 0 0 z\nwhat\nf 1 1 1\n";
+        let histogram = error_histogram::<f32, u32>(input);
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram["expected float"], 3);
+        assert_eq!(histogram["a face needs at least 3 vertices"], 2);
+        assert_eq!(histogram["unknown statement 'what'"], 1);
+        assert!(error_histogram::<f32, u32>("v 0 0 0\n").is_empty());
+    }
+
+    #[test]
+    fn test_spanned(){
+        let input = "v 1.0 2.0 3.0\n# comment\r\nf 1 1 1\n";
+        let res: Vec<(_, LineResult<f32, u32>)> = parse_file_spanned(input).collect();
+        assert_eq!(res.len(), 4);
+        let (span, line) = &res[2];
+        assert_eq!(span.start, input.find('f').unwrap());
+        assert_eq!(&input[span.clone()], "f 1 1 1");
+        assert!(matches!(line, LineResult::FaceLine(_)));
+        assert_eq!(res[3].0, input.len()..input.len());
+    }
+
+    #[test]
+    fn test_element_spans(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n  f 3 2 1   # back\r\n# done";
+        let res: Vec<(ElementSpan, LineResult<f32, u32>)> = parse_file_element_spans(input).collect();
+        let faces: Vec<&ElementSpan> = res.iter().filter(|(_, line)| matches!(line, LineResult::FaceLine(_))).map(|(span, _)| span).collect();
+        assert_eq!(faces[1], &ElementSpan{ line: 5, byte_range: 2..9 });
+        assert_eq!(&input.split('\n').nth(4).unwrap()[faces[1].byte_range.clone()], "f 3 2 1");
+        assert_eq!(res[5].0, ElementSpan{ line: 6, byte_range: 0..0 });
+    }
+
+    #[test]
+    fn test_parse_file_with_comments(){
+        let input = "v 1 2 3 # weld\r\n# just a comment\nf 1 1 1\nvp 1 #?";
+        let res: Vec<WithComment<f32, u32>> = parse_file_with_comments(input).collect();
+        assert!(matches!(res[0].result, LineResult::VertDataLine(VertexData::Coord3{ x: 1.0, y: 2.0, z: 3.0 })));
+        assert_eq!(res[0].comment, Some("weld"));
+        assert!(matches!(res[1].result, LineResult::NoData));
+        assert_eq!(res[1].comment, Some("just a comment"));
+        assert!(matches!(res[2].result, LineResult::FaceLine(_)));
+        assert_eq!(res[2].comment, None);
+        assert!(matches!(res[3].result, LineResult::Error(_)));
+        assert_eq!(res[3].comment, Some("?"));
+    }
+
+    #[test]
+    fn test_homogenize(){
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("v 2 4 -6 2.0").unwrap();
+        if let LineResult::VertDataLine(res) = res{
+            assert_eq!(res, VertexData::Coord4{x: 2.0, y: 4.0, z: -6.0, w: 2.0});
+            assert_eq!(res.homogenize(), Some([1.0, 2.0, -3.0]));
+        }else{ panic!("Wrong line type!"); }
+        assert_eq!(VertexData::Coord3{x: 1.0f32, y: 2.0, z: 3.0}.homogenize(), Some([1.0, 2.0, 3.0]));
+        assert_eq!(VertexData::Coord4{x: 1.0f32, y: 2.0, z: 3.0, w: 0.0}.homogenize(), None);
+        assert_eq!(VertexData::Normal{x: 1.0f32, y: 2.0, z: 3.0}.homogenize(), None);
+    }
+
+    #[test]
+    fn test_short_face(){
+        for line in ["f 1 2", "f 1/1/1"]{
+            let (_, res) : (_, LineResult<f32, u32>) = parse_line(line).unwrap();
+            assert!(matches!(res, LineResult::Error(e) if e == "a face needs at least 3 vertices"));
+        }
+        assert!(parse_line::<f32, u32>("f 1 2 x").is_err());
+    }
+
+    #[test]
+    fn test_empty_face(){
+        for line in ["f", "  f  ", "f # nothing\r"]{
+            let (_, res) : (_, LineResult<f32, u32>) = parse_line(line).unwrap();
+            assert!(matches!(res, LineResult::Error(e) if e == "empty face"));
+        }
+        let errors = parse_strict::<f32, u32>("v 1 2 3\nf\n").unwrap_err();
+        assert_eq!(errors, vec![ParseError{ line: 2, message: String::from("empty face") }]);
+    }
+
+    #[test]
+    fn test_parse_line_remainder(){
+        let (res, rest) : (LineResult<f32, u32>, _) = parse_line_remainder("v 1 2 3 extra stuff");
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::Coord3{..})));
+        assert_eq!(rest, " extra stuff");
+        let (res, rest) : (LineResult<f32, u32>, _) = parse_line_remainder("f 1 2 3 4 oops # hi");
+        assert!(matches!(res, LineResult::FaceLine(Face::Face4{..})));
+        assert_eq!(rest, " oops # hi");
+        let (res, rest) : (LineResult<f32, u32>, _) = parse_line_remainder("vn 0 1 0 # fine");
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::Normal{..})));
+        assert_eq!(rest, "");
+        let (res, rest) : (LineResult<f32, u32>, _) = parse_line_remainder("nonsense");
+        assert!(matches!(res, LineResult::Error(_)));
+        assert_eq!(rest, "nonsense");
+    }
+
+    #[test]
+    fn test_map_indices(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1/2/3 -1//2 3").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        let face: Face<usize> = face.map_indices(|i| resolve_index(i, 5).unwrap());
+        if let Face::Face3{ v1, v2, v3 } = face{
+            assert_eq!(v1, VertexIndeces{ coord_rindex: 0, texcoord_rindex: Some(1), normal_rindex: Some(2) });
+            assert_eq!(v2, VertexIndeces{ coord_rindex: 4, texcoord_rindex: None, normal_rindex: Some(1) });
+            assert_eq!(v3, VertexIndeces{ coord_rindex: 2, texcoord_rindex: None, normal_rindex: None });
+        }else{ panic!("Wrong face type!"); }
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1 2 3 4 5").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        let face = face.map_indices(|i| i as u32 * 10);
+        assert_eq!(face.vertices().map(|v| v.coord_rindex).collect::<Vec<_>>(), vec![10, 20, 30, 40, 50]);
+    }
+
+    #[test]
+    fn test_comment_crlf(){
+        let (rest, _) : (_, VertexData<f32>) = parse_coord3("v 1 2 3 # note\r", &ParseOptions::default()).unwrap();
+        assert_eq!(end_line(rest).unwrap().1, Some("note"));
+        assert_eq!(end_line(" #\r").unwrap().1, Some(""));
+        assert_eq!(end_line("\r").unwrap().1, None);
+    }
+
+    #[test]
+    fn test_skip_bad_vertices(){
+        let options = ParseOptions{ skip_bad_vertices: true, ..Default::default() };
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line_with_options("f 1/2/3 bad 4/5/6 7/8/9", &options).unwrap();
+        let LineResult::FaceLine(Face::Face3{ v1, v2, v3 }) = res else { panic!("Wrong line type!"); };
+        assert_eq!([v1.coord_rindex, v2.coord_rindex, v3.coord_rindex], [1, 4, 7]);
+        assert_eq!(v3, VertexIndeces{ coord_rindex: 7, texcoord_rindex: Some(8), normal_rindex: Some(9) });
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line_with_options("f 1 2x 3 4 5 # note", &options).unwrap();
+        assert!(matches!(res, LineResult::FaceLine(Face::Face4{ .. })));
+        assert!(parse_line_with_options::<f32, i32>("f 1 bad 2", &options).is_err()); // Still not enough for a face
+        assert!(parse_line::<f32, i32>("f 1/2/3 bad 4/5/6 7/8/9").is_err());
+    }
+
+    #[test]
+    fn test_long_face(){
+        let input = (1..=10_000).fold(String::from("f"), |line, i| line + &format!(" {}/{}", i, i)) + " # big";
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line(&input).unwrap();
+        let LineResult::FaceLine(Face::FaceN{ verts }) = res else { panic!("Wrong line type!"); };
+        assert_eq!(verts.len(), 10_000);
+        assert_eq!(verts.capacity(), 10_000); // Reserved up front, not grown
+        assert_eq!(verts[9_999], VertexIndeces{ coord_rindex: 10_000, texcoord_rindex: Some(10_000), normal_rindex: None });
+    }
+
+    #[test]
+    fn test_comment_hashes(){
+        assert_eq!(end_line("# a # b").unwrap().1, Some("a # b")); // Everything after the first # is the comment
+        assert_eq!(end_line("#a#b").unwrap().1, Some("a#b"));
+        let (rest, _) : (_, VertexData<f32>) = parse_coord3("v 1 2 3#a#b", &ParseOptions::default()).unwrap();
+        assert_eq!(end_line(rest).unwrap().1, Some("a#b"));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("#comment").unwrap();
+        assert!(matches!(res, LineResult::NoData));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("#a#b").unwrap();
+        assert!(matches!(res, LineResult::NoData));
+    }
+
+    fn _assert_send_sync<T: Send + Sync>(){}
+
+    // Compiling is the test, nothing in these types should stop them from being shared between threads
+    #[test]
+    fn test_send_sync(){
+        _assert_send_sync::<VertexData<f32>>();
+        _assert_send_sync::<VertexIndeces<i32>>();
+        _assert_send_sync::<Face<i32>>();
+        _assert_send_sync::<LineResult<'static, f32, i32>>();
+        _assert_send_sync::<ParseError>();
+        _assert_send_sync::<ParseOptions>();
+        _assert_send_sync::<Mesh<f32, i32>>();
+    }
+
+    #[test]
+    fn test_retain_kind(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\ng side\nf 1 2 3\n# two\nf 3 2 1\nvn 0 0 1\noops\n";
+        let faces: Vec<LineResult<f32, u32>> = parse_file(input).retain_kind(LineKind::Face).collect();
+        assert_eq!(faces.len(), 2);
+        assert!(faces.iter().all(|line| matches!(line, LineResult::FaceLine(_))));
+        assert_eq!(parse_file::<f32, u32>(input).retain_kind(LineKind::Error).count(), 1);
+    }
+
+    #[test]
+    fn test_clone(){
+        let input = String::from("v 1 2 3\ng side\nf 1 2 3\nbad\n");
+        let res: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+        let replay = res.clone();
+        assert_eq!(format!("{:?}", res), format!("{:?}", replay));
+        let owned: Vec<LineResult<'static, f32, u32>> = replay.into_iter().map(LineResult::into_owned).collect();
+        drop(res);
+        drop(input);
+        assert_eq!(owned.clone().len(), 5);
+        assert!(matches!(&owned[3], LineResult::Error(_)));
+    }
+
+    #[test]
+    fn test_min_len(){
+        let input: String = (0..1000).map(|i| format!("v {} {} {}\nf {} {} {}\n# {}\n", i, i, i, i, i, i, i)).collect();
+        let expected: Vec<String> = parse_file::<f32, u32>(&input).map(|res| format!("{:?}", res)).collect();
+        for min_len in [1, 7, 100, 1_000_000]{
+            let res: Vec<String> = parse_file_with_min_len::<f32, u32>(&input, min_len).map(|res| format!("{:?}", res)).collect();
+            assert_eq!(res, expected);
+        }
+    }
+
+    #[test]
+    fn test_trailing_newline(){
+        let res: Vec<LineResult<f32, u32>> = parse_file("v 1 2 3\nv 4 5 6\n").collect();
+        assert_eq!(res.len(), 3);
+        assert!(matches!(res[1], LineResult::VertDataLine(VertexData::Coord3{..})));
+        assert!(matches!(res[2], LineResult::NoData));
+    }
+
+    #[test]
+    fn test_no_trailing_newline(){
+        let res: Vec<LineResult<f32, u32>> = parse_file("v 1 2 3\nv 4 5 6").collect();
+        assert_eq!(res.len(), 2);
+        if let LineResult::VertDataLine(v) = &res[1]{
+            assert_eq!(*v, VertexData::Coord3{x: 4.0, y: 5.0, z: 6.0});
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_empty_input(){
+        // Like str::split, an empty input is a single empty line
+        let res: Vec<LineResult<f32, u32>> = parse_file("").collect();
+        assert_eq!(res.len(), 1);
+        assert!(matches!(res[0], LineResult::NoData));
+    }
+
+    #[test]
+    fn test_push_xyz(){
+        let mut buf = Vec::new();
+        push_xyz(&VertexData::Coord3{x: 1.0f32, y: 2.0, z: 3.0}, &mut buf);
+        assert_eq!(buf.len(), 3);
+        push_xyz(&VertexData::TextureCoord2{u: 0.5f32, v: 0.25}, &mut buf);
+        assert_eq!(buf.len(), 5);
+        push_xyz(&VertexData::Normal{x: 0.0f32, y: -1.0, z: 0.0}, &mut buf);
+        assert_eq!(buf, vec![1.0, 2.0, 3.0, 0.5, 0.25, 0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_context(){
+        let input = String::from("o cube\ng side # front\nusemtl brick\nmtllib cube.mtl");
+        let res: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+        let buf = input.as_bytes().as_ptr_range();
+        for line in &res{
+            let name = match line{
+                LineResult::Object(name) | LineResult::Group(name) | LineResult::Material(MaterialRef::UseMtl(Some(name))) => name,
+                LineResult::Material(MaterialRef::MtlLib(names)) => &names[0],
+                _ => panic!("Wrong line type!")
+            };
+            if let Cow::Borrowed(name) = name{
+                assert!(buf.contains(&name.as_ptr()));
+            }else{ panic!("Name was allocated!"); }
+        }
+        assert!(matches!(&res[1], LineResult::Group(name) if name == "side"));
+        assert!(matches!(&res[2], LineResult::Material(MaterialRef::UseMtl(Some(name))) if name == "brick"));
+
+        let owned: Vec<LineResult<'static, f32, u32>> = res.into_iter().map(LineResult::into_owned).collect();
+        drop(input);
+        assert!(matches!(&owned[0], LineResult::Object(Cow::Owned(name)) if name == "cube"));
+    }
+
+    #[test]
+    fn test_names_rest_of_line(){
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("o My/Object.001").unwrap();
+        assert!(matches!(res, LineResult::Object(name) if name == "My/Object.001"));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("g  left wing\t# the other one is missing\r").unwrap();
+        assert!(matches!(res, LineResult::Group(name) if name == "left wing"));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("usemtl \"Material #1\"").unwrap(); // Still a comment, even in quotes
+        assert!(matches!(res, LineResult::Material(MaterialRef::UseMtl(Some(name))) if name == "\"Material"));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("usemtl \"Brick Wall\" \r\n").unwrap();
+        assert!(matches!(res, LineResult::Material(MaterialRef::UseMtl(Some(name))) if name == "\"Brick Wall\""));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("mtllib a.mtl b.mtl").unwrap(); // A list, not one name
+        assert!(matches!(res, LineResult::Material(MaterialRef::MtlLib(names)) if names.len() == 2));
+    }
+
+    #[test]
+    fn test_comma_decimal(){
+        let options = ParseOptions{ comma_decimal: true, ..Default::default() };
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("v 1,5 2,0 3,0", &options).unwrap();
+        if let LineResult::VertDataLine(res) = res{
+            assert_eq!(res, VertexData::Coord3{x: 1.5, y: 2.0, z: 3.0});
+        }else{ panic!("Wrong line type!"); }
+        assert!(parse_line::<f32, u32>("v 1,5 2,0 3,0").is_err());
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("f 1 2 3", &options).unwrap();
+        assert!(matches!(res, LineResult::FaceLine(_)));
+    }
+
+    #[test]
+    fn test_skip_empty(){
+        let input = "\n".repeat(100);
+        let options = ParseOptions{ skip_empty: true, ..Default::default() };
+        assert_eq!(parse_file_with_options::<f32, u32>(&input, options.clone()).count(), 0);
+        assert_eq!(parse_file::<f32, u32>(&input).count(), 101);
+        assert_eq!(parse_file_with_options::<f32, u32>("# hi\n\nv 1 2 3\n  \n", options).count(), 1);
+    }
+
+    #[test]
+    fn test_texcoord_wrap(){
+        let clamp = ParseOptions{ texcoord_wrap: TexcoordWrap::Clamp, ..Default::default() };
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("vt -0.5 1.5", &clamp).unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord2{u, v}) if u == 0.0 && v == 1.0));
+        let repeat = ParseOptions{ texcoord_wrap: TexcoordWrap::Repeat, ..Default::default() };
+        let (_, res) : (_, LineResult<f64, u32>) = parse_line_with_options("vt -0.25 1.5 2", &repeat).unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord3{u, v, w}) if u == 0.75 && v == 0.5 && w == 0.0));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("vt 1 1", &repeat).unwrap(); // The edge of the texture, not wrapped to 0
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord2{u, v}) if u == 1.0 && v == 1.0));
+        let (_, res) : (_, LineResult<f64, u32>) = parse_line_with_options("vt 0 1 0.5", &repeat).unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord3{u, v, w}) if u == 0.0 && v == 1.0 && w == 0.5));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("v -0.5 1.5", &clamp).unwrap(); // Positions are left alone
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::Coord2{x, y}) if x == -0.5 && y == 1.5));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("vt -0.5 1.5").unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord2{u, v}) if u == -0.5 && v == 1.5));
+    }
+
+    #[test]
+    fn test_max_line_len(){
+        let options = ParseOptions{ max_line_len: Some(4096), ..Default::default() };
+        let line = format!("f{}", " 1".repeat(512 * 1024));
+        let err = parse_line_with_options::<f32, u32>(&line, &options).unwrap_err();
+        assert!(matches!(err, nom::Err::Error(e) if e.code == nom::error::ErrorKind::TooLarge));
+        assert!(parse_line::<f32, u32>(&line).is_ok());
+        let input = format!("v 1 2 3\n{}\nv 4 5 6\n", line);
+        let res: Vec<LineResult<f32, u32>> = parse_file_with_options(&input, options).collect();
+        assert!(matches!(&res[1], LineResult::Error(e) if e.len() < 100));
+        assert!(matches!(res[2], LineResult::VertDataLine(_)));
+    }
+
+    #[test]
+    fn test_parse_positions_only(){
+        let input = "v 1 2 3\nvn 0 1 0\nvt 0.5 0.5\nf 1 2 3\n  v 4 5\nv oops\n# v 7 8 9\nv 1 2 3 2\nvp 1 2 3\n";
+        let positions: Vec<VertexData<f32>> = parse_positions_only(input).collect();
+        assert_eq!(positions, vec![VertexData::Coord3{x: 1.0, y: 2.0, z: 3.0}, VertexData::Coord2{x: 4.0, y: 5.0}, VertexData::Coord4{x: 1.0, y: 2.0, z: 3.0, w: 2.0}]);
+        assert_eq!(parse_positions_only::<f32>("v 1 2 3\n\0\0\0\x01\x02garbage\n").count(), 0); // Not text
+    }
+
+    #[test]
+    fn test_parse_file_triangulated(){
+        let input = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1/1 2/1 3/1 4/1\nf 1 2\n# f 1 2 3\n";
+        let tris: Vec<[VertexIndeces<u32>; 3]> = parse_file_triangulated(input).collect();
+        assert_eq!(tris.len(), 3);
+        assert_eq!(tris.iter().map(|t| t.map(|v| v.coord_rindex)).collect::<Vec<_>>(), vec![[1, 2, 3], [1, 2, 3], [1, 3, 4]]);
+        assert_eq!(tris[2][2].texcoord_rindex, Some(1));
+        assert_eq!(parse_file_triangulated::<u32>("f 1 2 3\n\0\0\0\x01\x02garbage\n").count(), 0); // Not text
+    }
+
+    #[test]
+    fn test_strict(){
+        let strict = ParseOptions{ strict: true, ..Default::default() };
+        assert!(parse_line_with_options::<f32, u32>("v 1 2", &strict).is_err());
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("v 1 2 3", &strict).unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::Coord3{..})));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line_with_options("vt 1 2", &strict).unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::TextureCoord2{..})));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("v 1 2").unwrap();
+        assert!(matches!(res, LineResult::VertDataLine(VertexData::Coord2{..})));
+    }
+
+    #[test]
+    fn test_error_messages(){
+        let errors = parse_strict::<f32, u32>("v 1 2 3\nvn 1 2 # two\nv 1 x 3\nf 1 2 3/x\nwhat 1\nv 1 2 3 4 5\nv 1e999 0 0\ng\nf 1 2 99999999999\nf //1 //2 //3\n").unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, vec![
+            "line 2: expected float, found '#'",
+            "line 3: expected float, found 'x'",
+            "line 4: expected face vertex, found '3/x'",
+            "line 5: unknown statement 'what'",
+            "line 6: expected end of line, found '5'",
+            "line 7: number too large, found '1e999'",
+            "line 8: expected name, found end of line",
+            "line 9: number too large, found '99999999999'",
+            "line 10: missing coordinate index, found '//1'",
+        ]);
+        assert!(parse_face_vertex::<u32>("//3", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_as_error(){
+        let input = "cstype bezier\ncstype nurbs\nvp 0.5 0.5\nsurf 0 1 0 1 1 2 3 4";
+        let kinds = |options: ParseOptions| parse_file_with_options::<f32, i32>(input, options).map(|line| line.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds(ParseOptions::default()), vec![LineKind::FreeForm, LineKind::Error, LineKind::Error, LineKind::Error]);
+        // A cstype with a curve type that doesn't exist is still wrong, it's only statements that aren't known at all that get skipped
+        let skip = ParseOptions{ unknown_as_error: false, ..Default::default() };
+        assert_eq!(kinds(skip.clone()), vec![LineKind::FreeForm, LineKind::Error, LineKind::NoData, LineKind::NoData]);
+        assert_eq!(kinds(ParseOptions{ skip_empty: true, ..skip }), vec![LineKind::FreeForm, LineKind::Error]);
+    }
+
+    #[test]
+    fn test_parse_file_cancellable(){
+        let input: String = (0..20_000).map(|i| if i % 2 == 0 { format!("v {} 0 0\n", i) } else { String::from("f 1 2 3\n") }).collect();
+        let cancel = AtomicBool::new(false);
+        let all: Vec<LineResult<f32, u32>> = parse_file_cancellable(&input, &cancel);
+        assert_eq!(all.len(), 20_001);
+        assert_eq!(all.iter().map(LineResult::kind).collect::<Vec<_>>(), parse_file::<f32, u32>(&input).map(|line| line.kind()).collect::<Vec<_>>());
+        cancel.store(true, Ordering::Relaxed);
+        assert!(parse_file_cancellable::<f32, u32>(&input, &cancel).is_empty());
+        let binary = "\u{1}\n".repeat(1000);
+        assert!(matches!(&parse_file_cancellable::<f32, u32>(&binary, &cancel)[..], [LineResult::Error(e)] if e.contains("does not look like a text OBJ")));
+    }
+
+    #[test]
+    fn test_parse_file_cancellable_partway(){
+        // Parsing index 2 sets the flag, like a cancel button pressed on another thread while the file is being parsed
+        static CANCEL: AtomicBool = AtomicBool::new(false);
+        #[derive(Debug, Clone, Copy)]
+        struct Index;
+        impl FromStr for Index{
+            type Err = std::num::ParseIntError;
+            fn from_str(s: &str) -> Result<Self, Self::Err>{
+                if s.parse::<u32>()? == 2 { CANCEL.store(true, Ordering::Relaxed); }
+                Ok(Index)
+            }
+        }
+        let input = "f 1 1 1\n".repeat(50_000) + "f 2 2 2\n" + &"f 1 1 1\n".repeat(50_000);
+        let res: Vec<LineResult<f32, Index>> = parse_file_cancellable(&input, &CANCEL);
+        assert!(CANCEL.load(Ordering::Relaxed));
+        // Nothing past the chunk with the cancelling line, and a prefix of the faces parse_file would give
+        assert!(res.len() <= 50_001 + COUNT_CHUNK_LEN / 8 + 1);
+        assert!(res.iter().all(|line| matches!(line, LineResult::FaceLine(Face::Face3{..}))));
+    }
+
+    #[test]
+    fn test_summarize(){
+        let input = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0.5 2 0\nvt 0 0\nvn 0 0 1\nf 1 2 3\nf 1 2 3 4\nf 1 2 3 5 4\nf 1 2\nvp 1\n# done\n";
+        assert_eq!(summarize(input), Summary{ positions: 5, texcoords: 1, normals: 1, faces: 3, triangles: 1 + 2 + 3, errors: 2 });
+    }
+
+    #[test]
+    fn test_count_elements(){
+        let counts = count_elements("v 1 2 3\n  v 4 5 6\nvt 0 0\nvn 0 1 0\n# v 1 2 3\nf 1 2 3\nf 1 2 3 4\nvp 1\n");
+        assert_eq!(counts, ElementCounts{ positions: 2, texcoords: 1, normals: 1, faces: 2 });
+    }
+
+    #[test]
+    fn test_classify_lines(){
+        let input = "# cube\nmtllib cube.mtl\no cube\n  v 1 2 3\nvt 0 0\r\nvn 0 1 0\ng side\nusemtl red\nf 1/1/1 2/2/1 3/3/1\ncstype bezier\nvp 1\n\nf# odd\nv not a number";
+        assert_eq!(classify_lines(input), vec![
+            LineKind::NoData, LineKind::Material, LineKind::Object, LineKind::VertData, LineKind::VertData, LineKind::VertData, LineKind::Group,
+            LineKind::Material, LineKind::Face, LineKind::FreeForm, LineKind::Error, LineKind::NoData, LineKind::Face, LineKind::VertData
+        ]);
+        // Agrees with parse_file on lines that do parse
+        let valid: String = input.lines().take(10).collect::<Vec<_>>().join("\n");
+        let parsed: Vec<LineKind> = parse_file::<f32, i32>(&valid).map(|line| line.kind()).collect();
+        assert_eq!(classify_lines(&valid), parsed);
+    }
+
+    #[test]
+    fn test_count_elements_naive(){
+        let naive = |input: &str|{
+            let mut counts = ElementCounts::default();
+            for line in input.split('\n'){
+                match line.trim_start().split([' ', '\t']).next(){
+                    Some("v") => counts.positions += 1,
+                    Some("vt") => counts.texcoords += 1,
+                    Some("vn") => counts.normals += 1,
+                    Some("f") => counts.faces += 1,
+                    _ => {}
+                }
+            }
+            counts
+        };
+        let input: String = (0..20_000).map(|i| match i % 7{
+            0 => format!("v {} 0 0\r\n", i),
+            1 => String::from("\tvt 0.5 0.5\n"),
+            2 => String::from("vn 0 0 1\n"),
+            3 => String::from("  f 1 2 3\n"),
+            4 => String::from("# v 1 2 3\n\n"),
+            5 => String::from("vp 1\nv\n"),
+            _ => String::from("f\t1 2 3 4"),
+        } + "\n").collect();
+        assert!(input.len() > 3 * COUNT_CHUNK_LEN);
+        assert_eq!(count_elements(&input), naive(&input));
+        assert_eq!(count_elements(&input[..input.len() - 1]), naive(&input[..input.len() - 1]));
+        assert_eq!(line_count(&input), input.split('\n').count());
+        assert_eq!(line_count(""), 1);
+        assert_eq!(count_elements(""), ElementCounts::default());
+    }
+
+    #[test]
+    fn test_binary_input(){
+        let mut state: u32 = 12345;
+        let bytes: Vec<u8> = (0..100_000).map(|_|{ state = state.wrapping_mul(1103515245).wrapping_add(12345); (state >> 16) as u8 }).collect();
+        let input = String::from_utf8_lossy(&bytes);
+        assert!(!looks_like_text(&input));
+        let res: Vec<LineResult<f32, u32>> = parse_file(&input).collect();
+        assert_eq!(res.len(), 1);
+        assert!(matches!(&res[0], LineResult::Error(e) if e.contains("does not look like a text OBJ")));
+
+        // Every entry point gives that one error, not one per line
+        let input = "\u{1}\n".repeat(1000);
+        let not_text = |res: &LineResult<f32, u32>| matches!(res, LineResult::Error(e) if e.contains("does not look like a text OBJ"));
+        let errors = parse_errors::<f32, u32>(&input);
+        assert!(errors.len() == 1 && errors[0].line == 1 && errors[0].message.contains("does not look like a text OBJ"));
+        assert_eq!(parse_strict::<f32, u32>(&input).unwrap_err().len(), 1);
+        let res: Vec<_> = parse_file_with_min_len(&input, 16).collect();
+        assert!(res.len() == 1 && not_text(&res[0]));
+        let res: Vec<_> = parse_file_spanned(&input).collect();
+        assert!(res.len() == 1 && res[0].0 == (0..input.len()) && not_text(&res[0].1));
+        let res: Vec<_> = parse_file_element_spans(&input).collect();
+        assert!(res.len() == 1 && res[0].0.line == 1 && not_text(&res[0].1));
+        let res: Vec<_> = parse_file_with_comments(&input).collect();
+        assert!(res.len() == 1 && not_text(&res[0].result) && res[0].comment.is_none());
+        let res = parse_file_cancellable(&input, &AtomicBool::new(false));
+        assert!(res.len() == 1 && not_text(&res[0]));
+
+        assert!(looks_like_text("v 1 2 3\r\n\tvn 0 0 1\n# caf\u{e9}\n"));
+        assert!(looks_like_text(""));
+    }
+
+    #[test]
+    fn test_usemtl_none(){
+        for line in ["usemtl", "usemtl  # no material", "usemtl (null)"]{
+            let (_, res) : (_, LineResult<f32, u32>) = parse_line(line).unwrap();
+            assert!(matches!(res, LineResult::Material(MaterialRef::UseMtl(None))));
+        }
+        assert!(parse_line::<f32, u32>("usemtlx").is_err());
+    }
+
+    #[test]
+    fn test_mtllib_multiple(){
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("mtllib base.mtl extra.mtl # two of them").unwrap();
+        if let LineResult::Material(MaterialRef::MtlLib(names)) = res{
+            assert_eq!(names, vec!["base.mtl", "extra.mtl"]);
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_texture_map_ref(){
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("usemap brick").unwrap();
+        assert!(matches!(res, LineResult::TextureMap(TextureMapRef::UseMap(name)) if name == "brick"));
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("maplib textures.map").unwrap();
+        if let LineResult::TextureMap(TextureMapRef::MapLib(names)) = res{
+            assert_eq!(names, vec!["textures.map"]);
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_facen(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1 2 3 4 5 -1/2/3").unwrap();
+        if let LineResult::FaceLine(face) = res{
+            assert!(matches!(face, Face::FaceN{..}));
+            assert_eq!(face.len(), 6);
+            assert_eq!(face.vertex(5), Some(&VertexIndeces{ coord_rindex: -1, texcoord_rindex: Some(2), normal_rindex: Some(3) }));
+            assert_eq!(face.triangles().count(), 4);
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_lenient_slashes(){
+        let options = ParseOptions{ lenient_slashes: true, ..Default::default() };
+        let (_, res) : (_, VertexIndeces<i32>) = parse_face_vertex("1 / 2 / 3", &options).unwrap();
+        assert_eq!(res, VertexIndeces{ coord_rindex: 1, texcoord_rindex: Some(2), normal_rindex: Some(3) });
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line_with_options("f 1 / 2 / 3 2 / 3 / 4 3 // 5", &options).unwrap();
+        if let LineResult::FaceLine(Face::Face3{ v1, v2, v3 }) = res{
+            assert_eq!(v1, VertexIndeces{ coord_rindex: 1, texcoord_rindex: Some(2), normal_rindex: Some(3) });
+            assert_eq!(v2, VertexIndeces{ coord_rindex: 2, texcoord_rindex: Some(3), normal_rindex: Some(4) });
+            assert_eq!(v3, VertexIndeces{ coord_rindex: 3, texcoord_rindex: None, normal_rindex: Some(5) });
+        }else{ panic!("Wrong line type!"); }
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line_with_options("f 1 2 3", &options).unwrap();
+        assert!(matches!(res, LineResult::FaceLine(Face::Face3{ .. })));
+        assert!(parse_line::<f32, i32>("f 1 / 2 / 3 2 / 3 / 4 3 // 5").is_err());
+        assert!(parse_line::<f32, i32>("f 1 / 2 / 3").is_err());
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line_with_options("f 1 / 2 / 3", &options).unwrap(); // A single vertex
+        assert!(matches!(res, LineResult::Error(e) if e == "a face needs at least 3 vertices"));
+    }
+
+    #[test]
+    fn test_negative_texcoord_normal_indices(){
+        let (rest, res) : (_, LineResult<f32, i32>) = parse_line("f 1/-2/-3 4/-5/-6 7/-8/-9").unwrap();
+        assert_eq!(rest, "");
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        assert_eq!(face.vertices().map(|v| (v.coord_rindex, v.texcoord_rindex, v.normal_rindex)).collect::<Vec<_>>(), vec![
+            (1, Some(-2), Some(-3)), (4, Some(-5), Some(-6)), (7, Some(-8), Some(-9))
+        ]);
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1//-3 4//-6 7//-9").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        assert_eq!(face.vertex(2), Some(&VertexIndeces{ coord_rindex: 7, texcoord_rindex: None, normal_rindex: Some(-9) }));
+        // Unsigned indices can't hold them
+        assert!(matches!(line_result::<f32, u32>("f 1/-2/-3 4/-5/-6 7/-8/-9", &ParseOptions::default()), LineResult::Error(_)));
+    }
+
+    #[test]
+    fn test_resolve_negative_slots(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1/-1/-1 2/-2/-3 -1/1/1").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        let resolved: Vec<_> = face.vertices().map(|v| v.resolve(4, 2, 3).unwrap()).collect(); // Each slot counts back from its own end
+        assert_eq!(resolved, vec![
+            VertexIndeces{ coord_rindex: 0, texcoord_rindex: Some(1), normal_rindex: Some(2) },
+            VertexIndeces{ coord_rindex: 1, texcoord_rindex: Some(0), normal_rindex: Some(0) },
+            VertexIndeces{ coord_rindex: 3, texcoord_rindex: Some(0), normal_rindex: Some(0) },
+        ]);
+        assert_eq!(face.vertex(1).unwrap().resolve(4, 1, 3), None); // -2 tex coords with only 1 of them
+    }
+
+    #[test]
+    fn test_compact_vertex_indeces(){
+        assert_eq!(std::mem::size_of::<Option<CompactVertexIndeces>>(), std::mem::size_of::<CompactVertexIndeces>());
+        let v = VertexIndeces{ coord_rindex: 3i32, texcoord_rindex: None, normal_rindex: Some(7) };
+        let compact = v.compact().unwrap();
+        assert_eq!(compact.normal_rindex, NonZeroU32::new(7));
+        assert_eq!(VertexIndeces::from(compact), VertexIndeces{ coord_rindex: 3u32, texcoord_rindex: None, normal_rindex: Some(7) });
+        assert_eq!(VertexIndeces{ coord_rindex: 3i32, texcoord_rindex: Some(-1), normal_rindex: None }.compact(), None);
+        assert_eq!(VertexIndeces{ coord_rindex: 0u64, texcoord_rindex: None, normal_rindex: None }.compact(), None);
+    }
+
+    #[test]
+    fn test_triangles_with_policy(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1/1/1 2/2/1 3/3 4/4/1").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        let kept: Vec<_> = face.triangles_with_policy(PartialAttributes::Keep).unwrap().collect();
+        assert_eq!(kept, face.triangles().collect::<Vec<_>>());
+        assert_eq!(kept[1][1].normal_rindex, None);
+        let dropped: Vec<_> = face.triangles_with_policy(PartialAttributes::Drop).unwrap().collect();
+        assert_eq!(dropped.len(), 2);
+        assert!(dropped.iter().flatten().all(|v| v.normal_rindex.is_none() && v.texcoord_rindex.is_some()));
+        assert!(face.triangles_with_policy(PartialAttributes::Reject).is_none());
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1//1 2//1 3//1 4//1").unwrap();
+        let LineResult::FaceLine(face) = res else { panic!("Wrong line type!"); };
+        assert_eq!(face.triangles_with_policy(PartialAttributes::Reject).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_with_defaults(){
+        let (_, res) : (_, VertexIndeces<i32>) = parse_face_vertex("3//2", &ParseOptions::default()).unwrap();
+        assert_eq!(res.with_defaults(1, 1), VertexIndeces{ coord_rindex: 3, texcoord_rindex: Some(1), normal_rindex: Some(2) });
+        let (_, res) : (_, VertexIndeces<i32>) = parse_face_vertex("3/4/5", &ParseOptions::default()).unwrap();
+        assert_eq!(res.with_defaults(1, 1), res);
+    }
+
+    #[test]
+    fn test_degenerate_face(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1 1 2").unwrap();
+        assert!(matches!(res, LineResult::FaceLine(f) if f.is_degenerate()));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1/1 2/1 3/1 4/2 5 2/2").unwrap();
+        assert!(matches!(res, LineResult::FaceLine(f) if f.is_degenerate()));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("f 1/1 2/1 3/1").unwrap();
+        assert!(matches!(res, LineResult::FaceLine(f) if !f.is_degenerate()));
+    }
+
+    #[test]
+    fn test_quad_fan(){
+        assert_eq!(fan_triangles(4).flatten().collect::<Vec<_>>(), vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(fan_triangles(3).count(), 1);
+        assert_eq!(fan_triangles(2).count(), 0);
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("f 1 2 3 4").unwrap();
+        if let LineResult::FaceLine(face) = res{
+            assert_eq!(face.triangles().flatten().map(|v| v.coord_rindex).collect::<Vec<_>>(), vec![1, 2, 3, 1, 3, 4]);
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn test_facen_inline(){
+        let v = VertexIndeces{ coord_rindex: 1u32, texcoord_rindex: None, normal_rindex: None };
+        let verts: FaceVertices<u32> = [v; 4].into_iter().collect();
+        assert!(!verts.spilled());
+        let (_, res) : (_, LineResult<f32, u32>) = parse_line("f 1 2 3 4 5 6").unwrap();
+        if let LineResult::FaceLine(Face::FaceN{ verts }) = res{
+            assert!(!verts.spilled());
+        }else{ panic!("Wrong line type!"); }
+    }
+
+    #[test]
+    fn test_freeform(){
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("cstype bezier").unwrap();
+        assert!(matches!(res, LineResult::FreeFormLine(FreeForm::CsType{ rational: false, kind: CurveType::Bezier })));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("cstype rat bspline").unwrap();
+        assert!(matches!(res, LineResult::FreeFormLine(FreeForm::CsType{ rational: true, kind: CurveType::BSpline })));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("deg 3").unwrap();
+        assert!(matches!(res, LineResult::FreeFormLine(FreeForm::Degree{ u: 3, v: None })));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("deg 3 2").unwrap();
+        assert!(matches!(res, LineResult::FreeFormLine(FreeForm::Degree{ u: 3, v: Some(2) })));
+        let (_, res) : (_, LineResult<f32, i32>) = parse_line("curv 0.0 1.0 1 2 -1").unwrap();
+        if let LineResult::FreeFormLine(res) = res{
+            assert_eq!(res, FreeForm::Curve{ u0: 0.0, u1: 1.0, control_points: vec![1, 2, -1] });
+        }else{ panic!("Wrong line type!"); }
+    }
 }
 
-// A line can either contain vertex info or face info as far as this parser is concerned
-#[derive(Debug)]
-pub enum LineResult<T, I>{
+// Names borrow from the input, call into_owned() if they need to outlive it
+#[derive(Debug, PartialEq, Clone)]
+pub enum MaterialRef<'a>{
+    UseMtl(Option<Cow<'a, str>>), // None for usemtl with no name (or "(null)"), which goes back to no material
+    MtlLib(Vec<Cow<'a, str>>) // mtllib can list several files
+}
+
+impl MaterialRef<'_>{
+    pub fn into_owned(self) -> MaterialRef<'static>{
+        match self{
+            MaterialRef::UseMtl(name) => MaterialRef::UseMtl(name.map(|name| Cow::Owned(name.into_owned()))),
+            MaterialRef::MtlLib(names) => MaterialRef::MtlLib(names.into_iter().map(|name| Cow::Owned(name.into_owned())).collect()),
+        }
+    }
+}
+
+// usemap/maplib, the texture map counterparts of usemtl/mtllib
+#[derive(Debug, PartialEq, Clone)]
+pub enum TextureMapRef<'a>{
+    UseMap(Cow<'a, str>), // "off" turns the map off again
+    MapLib(Vec<Cow<'a, str>>)
+}
+
+impl TextureMapRef<'_>{
+    pub fn into_owned(self) -> TextureMapRef<'static>{
+        match self{
+            TextureMapRef::UseMap(name) => TextureMapRef::UseMap(Cow::Owned(name.into_owned())),
+            TextureMapRef::MapLib(names) => TextureMapRef::MapLib(names.into_iter().map(|name| Cow::Owned(name.into_owned())).collect()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CurveType{
+    BMatrix,
+    Bezier,
+    BSpline,
+    Cardinal,
+    Taylor
+}
+
+// Only the basics of free-form geometry, so files using it don't produce errors
+#[derive(Debug, PartialEq, Clone)]
+pub enum FreeForm<T, I>{
+    CsType{ rational: bool, kind: CurveType },
+    Degree{ u: u32, v: Option<u32> },
+    Curve{ u0: T, u1: T, control_points: Vec<I> }
+}
+
+// A line can either contain vertex info, face info, free-form info or context (group, object, material) as far as this parser is concerned
+#[derive(Debug, Clone)]
+pub enum LineResult<'a, T, I>{
     VertDataLine(VertexData<T>),
     FaceLine(Face<I>),
+    Group(Cow<'a, str>),
+    Object(Cow<'a, str>),
+    Material(MaterialRef<'a>),
+    TextureMap(TextureMapRef<'a>),
+    FreeFormLine(FreeForm<T, I>),
     NoData,
     Error(String)
 }
 
+impl<T, I> LineResult<'_, T, I>{
+    // Copies any names borrowed from the input so the result no longer depends on it
+    pub fn into_owned(self) -> LineResult<'static, T, I>{
+        match self{
+            LineResult::VertDataLine(v) => LineResult::VertDataLine(v),
+            LineResult::FaceLine(f) => LineResult::FaceLine(f),
+            LineResult::Group(name) => LineResult::Group(Cow::Owned(name.into_owned())),
+            LineResult::Object(name) => LineResult::Object(Cow::Owned(name.into_owned())),
+            LineResult::Material(m) => LineResult::Material(m.into_owned()),
+            LineResult::TextureMap(m) => LineResult::TextureMap(m.into_owned()),
+            LineResult::FreeFormLine(f) => LineResult::FreeFormLine(f),
+            LineResult::NoData => LineResult::NoData,
+            LineResult::Error(e) => LineResult::Error(e),
+        }
+    }
+
+    pub fn kind(&self) -> LineKind{
+        match self{
+            LineResult::VertDataLine(_) => LineKind::VertData,
+            LineResult::FaceLine(_) => LineKind::Face,
+            LineResult::Group(_) => LineKind::Group,
+            LineResult::Object(_) => LineKind::Object,
+            LineResult::Material(_) => LineKind::Material,
+            LineResult::TextureMap(_) => LineKind::TextureMap,
+            LineResult::FreeFormLine(_) => LineKind::FreeForm,
+            LineResult::NoData => LineKind::NoData,
+            LineResult::Error(_) => LineKind::Error,
+        }
+    }
+}
+
+// Which variant a LineResult is, without its data
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum LineKind{
+    VertData,
+    Face,
+    Group,
+    Object,
+    Material,
+    TextureMap,
+    FreeForm,
+    NoData,
+    Error
+}
+
+// Adapters for the parallel iterators parse_file and friends return
+pub trait LineResultsExt<'a, T, I>: ParallelIterator<Item = LineResult<'a, T, I>> + Sized
+where T: Send, I: Send{
+    // Keeps only the lines of the given kind, like only the faces
+    fn retain_kind(self, kind: LineKind) -> impl ParallelIterator<Item = LineResult<'a, T, I>>{
+        self.filter(move |line| line.kind() == kind)
+    }
+}
+
+impl<'a, T, I, P> LineResultsExt<'a, T, I> for P
+where T: Send, I: Send, P: ParallelIterator<Item = LineResult<'a, T, I>>{}
+
+// An error along with the (1-based) line it happened on
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError{
+    pub line: usize,
+    pub message: String
+}
+
+impl std::fmt::Display for ParseError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError{}
+
+// Knobs for parsing files that don't quite follow the spec, the default is to follow it
+#[derive(Debug, Clone)]
+pub struct ParseOptions{
+    // Accept ',' as the decimal point in floats (v 1,5 2,0 3,0), OBJ never uses ',' as a separator so this is unambiguous
+    pub comma_decimal: bool,
+    // Don't yield NoData for blank and comment only lines at all
+    pub skip_empty: bool,
+    // What to do with tex coords outside of [0, 1], for hardware that can't wrap them itself
+    pub texcoord_wrap: TexcoordWrap,
+    // Only accept what the spec allows, for now that means v with 2 floats (Coord2) is an error
+    pub strict: bool,
+    // Allow spaces around the '/'s in face vertices (f 1 / 2 / 3 ...), which some broken exporters write
+    pub lenient_slashes: bool,
+    // Lines longer than this (in bytes) are errors without being looked at, guards against huge lines in untrusted files
+    pub max_line_len: Option<usize>,
+    // Drop the words of a face that aren't vertices (f 1/2/3 bad 4/5/6 7/8/9 becomes a triangle) instead of failing the line
+    pub skip_bad_vertices: bool,
+    // Lines with a statement this crate doesn't know (vp, surf, ...) are errors, otherwise they're NoData, malformed known statements are always errors
+    pub unknown_as_error: bool,
+    // The index of the first element, 1 as the spec says but a few tools write 0-based files, positive face (and curv) indices
+    // are shifted to be 1-based while parsing, so Mesh, resolve_index and the writer treat them like any other file's
+    pub index_base: u32
+}
+
+impl Default for ParseOptions{
+    fn default() -> Self {
+        Self{
+            comma_decimal: false,
+            skip_empty: false,
+            texcoord_wrap: TexcoordWrap::default(),
+            strict: false,
+            lenient_slashes: false,
+            max_line_len: None,
+            skip_bad_vertices: false,
+            unknown_as_error: true,
+            index_base: 1
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TexcoordWrap{
+    #[default]
+    Keep,
+    Clamp, // -0.5 becomes 0.0, 1.5 becomes 1.0
+    Repeat // -0.25 becomes 0.75, 1.5 becomes 0.5, values already in [0, 1] are kept (so 1.0 stays 1.0)
+}
+
+// The number types coordinates can be parsed as, f32 and f64 (and Exact of them, see writer.rs), implement it to parse into another float type
+pub trait ObjFloat: Sized{
+    // The value of a number as consume_num matched it, None if it's malformed
+    fn from_num(num: &str) -> Option<Self>;
+    // consume_num never matches "inf", so an infinite value means the number overflowed, parse_float reports it as ErrorKind::TooLarge
+    fn is_infinite(&self) -> bool;
+    // For TexcoordWrap::Clamp and TexcoordWrap::Repeat
+    fn clamp_unit(self) -> Self;
+    fn repeat_unit(self) -> Self;
+}
+
+// With the fast-float feature f32 and f64 are parsed with lexical-core instead of FromStr
+impl ObjFloat for f32{
+    #[cfg(not(feature = "fast-float"))]
+    fn from_num(num: &str) -> Option<Self>{ num.parse().ok() }
+    #[cfg(feature = "fast-float")]
+    fn from_num(num: &str) -> Option<Self>{ lexical_core::parse(num.as_bytes()).ok() }
+    fn is_infinite(&self) -> bool{ (*self).is_infinite() }
+    fn clamp_unit(self) -> Self{ self.clamp(0.0, 1.0) }
+    fn repeat_unit(self) -> Self{ if (0.0..=1.0).contains(&self) { self } else { self.rem_euclid(1.0) } }
+}
+
+impl ObjFloat for f64{
+    #[cfg(not(feature = "fast-float"))]
+    fn from_num(num: &str) -> Option<Self>{ num.parse().ok() }
+    #[cfg(feature = "fast-float")]
+    fn from_num(num: &str) -> Option<Self>{ lexical_core::parse(num.as_bytes()).ok() }
+    fn is_infinite(&self) -> bool{ (*self).is_infinite() }
+    fn clamp_unit(self) -> Self{ self.clamp(0.0, 1.0) }
+    fn repeat_unit(self) -> Self{ if (0.0..=1.0).contains(&self) { self } else { self.rem_euclid(1.0) } }
+}
+
+fn wrap_texcoord<T>(val: T, wrap: TexcoordWrap) -> T
+where T: ObjFloat{
+    match wrap{
+        TexcoordWrap::Keep => val,
+        TexcoordWrap::Clamp => val.clamp_unit(),
+        TexcoordWrap::Repeat => val.repeat_unit()
+    }
+}
+
 // Note: Basically only parallel function
-pub fn parse_file<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = LineResult<T, I>> + 'input /* can't iterate if the input is gone */
-where T: Send + FromStr, I: Send + FromStr{
-    input.par_split('\n')
-    .map(|line|
-        parse_line(line)
-        .map(|(_unconsumed, parsed)| parsed)
-        .unwrap_or_else(|e|{
-            LineResult::Error(e.to_string())
+pub fn parse_file<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = LineResult<'input, T, I>> + 'input /* can't iterate if the input is gone */
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    parse_file_with_options(input, ParseOptions::default())
+}
+
+// If the input doesn't look like text (see looks_like_text) this yields a single error instead of one per line
+pub fn parse_file_with_options<'input, T, I>(input: &'input str, options: ParseOptions) -> impl ParallelIterator<Item = LineResult<'input, T, I>> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once(error)); }
+    let skip_empty = options.skip_empty;
+    Either::Left(
+        input.par_split('\n')
+        .map(move |line| line_result(line, &options))
+        .filter(move |res| !(skip_empty && matches!(res, LineResult::NoData)))
+    )
+}
+
+// Same as parse_file, but stops early once cancel is set (from another thread, like a UI's cancel button)
+// The input is parsed in chunks of about COUNT_CHUNK_LEN bytes and the flag is checked before each one, if it was set the results
+// are only the lines before the first chunk that didn't get parsed, so they're a prefix of what parse_file gives for text input
+// Input that doesn't look like text isn't parsed at all, it gives the same single error as parse_file, whether cancelled or not
+pub fn parse_file_cancellable<'input, T, I>(input: &'input str, cancel: &AtomicBool) -> Vec<LineResult<'input, T, I>>
+where T: Send + FromStr + ObjFloat, I: Send + FromStr{
+    if let Some(error) = not_text_error(input) { return vec![error]; }
+    let chunks = newline_chunks(input.as_bytes());
+    let last = chunks.len() - 1;
+    let parsed: Vec<Option<Vec<LineResult<T, I>>>> = chunks.into_par_iter().enumerate()
+    .map(|(n, chunk)|{
+        if cancel.load(Ordering::Relaxed) { return None; }
+        let start = chunk.as_ptr() as usize - input.as_ptr() as usize; // Chunks are subslices of the input
+        let chunk = &input[start..start+chunk.len()];
+        // Every chunk but the last ends right after a '\n', which doesn't start another line
+        let chunk = if n == last { chunk } else { &chunk[..chunk.len()-1] };
+        Some(chunk.split('\n').map(|line| line_result(line, &ParseOptions::default())).collect())
+    })
+    .collect();
+    parsed.into_iter().map_while(|lines| lines).flatten().collect()
+}
+
+// How many chars at the start of the input looks_like_text checks
+const TEXT_SAMPLE_LEN: usize = 1024;
+
+// Cheap check to avoid churning through binary files (a .stl or .png given by mistake), looks at the start of the input
+// and considers it binary if more than 1 in 10 chars are control characters (that aren't whitespace) or invalid utf8 that got replaced
+pub fn looks_like_text(input: &str) -> bool{
+    let (mut total, mut bad) = (0usize, 0usize);
+    for c in input.chars().take(TEXT_SAMPLE_LEN){
+        total += 1;
+        if c == char::REPLACEMENT_CHARACTER || (c.is_control() && !c.is_whitespace()) { bad += 1; }
+    }
+    bad * 10 <= total
+}
+
+// The error every parse function gives as its only result when the input doesn't look like text, None if it does
+pub(crate) fn not_text_error<'a, T, I>(input: &str) -> Option<LineResult<'a, T, I>>{
+    (!looks_like_text(input)).then(|| LineResult::Error(String::from("input does not look like a text OBJ")))
+}
+
+// Same as parse_file, but each rayon task gets at least min_len lines, so the task size can be tuned
+// Note: Has to find all the line breaks upfront, sequentially
+pub fn parse_file_with_min_len<'input, T, I>(input: &'input str, min_len: usize) -> impl IndexedParallelIterator<Item = LineResult<'input, T, I>> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once(error)); }
+    Either::Left(
+        input.split('\n').collect::<Vec<_>>()
+        .into_par_iter()
+        .with_min_len(min_len)
+        .map(|line| line_result(line, &ParseOptions::default()))
+    )
+}
+
+// Same as parse_file, but also gives the byte range each line occupies in the input (not including the '\n')
+// The error for input that doesn't look like text spans all of it
+pub fn parse_file_spanned<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = (Range<usize>, LineResult<'input, T, I>)> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once((0..input.len(), error))); }
+    Either::Left(
+        input.par_split('\n')
+        .map(move |line|{
+            let start = line.as_ptr() as usize - input.as_ptr() as usize; // Lines are subslices of the input
+            (start..start+line.len(), line_result(line, &ParseOptions::default()))
         })
     )
 }
 
+// Where an element is in the input, for mapping it back to the text, see parse_file_element_spans
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ElementSpan{
+    pub line: usize, // 1-based, like ParseError
+    pub byte_range: Range<usize> // Within the line, without surrounding whitespace or a comment, empty for lines with nothing on them
+}
+
+// Same as parse_file_spanned, but the spans are of the statements within their lines
+// The error for input that doesn't look like text is on line 1, with an empty span
+pub fn parse_file_element_spans<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = (ElementSpan, LineResult<'input, T, I>)> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once((ElementSpan{ line: 1, byte_range: 0..0 }, error))); }
+    Either::Left(
+        input.split('\n').collect::<Vec<_>>()
+        .into_par_iter()
+        .enumerate()
+        .map(|(n, line)|{
+            let content = &line[..line.find('#').unwrap_or(line.len())];
+            let start = content.len() - content.trim_start().len();
+            let end = content.trim_end().len().max(start);
+            (ElementSpan{ line: n + 1, byte_range: start..end }, line_result(line, &ParseOptions::default()))
+        })
+    )
+}
+
+// A line along with the text of its comment, if it has one, see parse_file_with_comments
+#[derive(Debug, Clone)]
+pub struct WithComment<'a, T, I>{
+    pub result: LineResult<'a, T, I>,
+    pub comment: Option<&'a str> // Without the '#' and surrounding whitespace (like end_line gives it), Some("") for a lone '#'
+}
 
-pub fn parse_line<T, I>(input: &str) -> IResult<&str, LineResult<T, I>>
-where T: FromStr, I: FromStr{
+// Same as parse_file, but keeps the comments instead of dropping them, for files that store metadata in them (v 1 2 3 # weld)
+// Names and numbers can't contain a '#', so the first one on the line is where the comment starts, even on lines that didn't parse
+pub fn parse_file_with_comments<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = WithComment<'input, T, I>> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once(WithComment{ result: error, comment: None })); }
+    Either::Left(
+        input.par_split('\n')
+        .map(|line| WithComment{
+            result: line_result(line, &ParseOptions::default()),
+            comment: line.find('#').map(|i| line[i+1..].trim())
+        })
+    )
+}
+
+// All or nothing, Ok only if every line parsed, otherwise every error (with its line, see ParseError) in order
+pub fn parse_strict<'input, T, I>(input: &'input str) -> Result<Vec<LineResult<'input, T, I>>, Vec<ParseError>>
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    let results: Vec<LineResult<T, I>> = parse_file(input).collect();
+    let errors: Vec<ParseError> = results.iter().enumerate().filter_map(|(i, res)| match res{
+        LineResult::Error(message) => Some(ParseError{ line: i + 1, message: message.clone() }),
+        _ => None
+    }).collect();
+    if errors.is_empty() { Ok(results) } else { Err(errors) }
+}
+
+// Only the errors of the input, parsed in parallel but always sorted by line (unlike printing them from inside a parallel iterator)
+pub fn parse_errors<T, I>(input: &str) -> Vec<ParseError>
+where T: Send + FromStr + ObjFloat, I: Send + FromStr{
+    if let Some(LineResult::Error(message)) = not_text_error::<T, I>(input) { return vec![ParseError{ line: 1, message }]; }
+    let mut errors: Vec<ParseError> = input.split('\n').collect::<Vec<_>>()
+    .into_par_iter()
+    .enumerate()
+    .filter_map(|(n, line)| match line_result::<T, I>(line, &ParseOptions::default()){
+        LineResult::Error(message) => Some(ParseError{ line: n + 1, message }),
+        _ => None
+    })
+    .collect();
+    errors.sort_by_key(|e| e.line); // Already in order since collect keeps it, but that's what this promises
+    errors
+}
+
+// How many lines failed for each kind of error, to spot an exporter getting the same thing wrong everywhere
+// The kind is the error message without what was found, so "expected float, found 'x'" and "expected float, found '#'" are the same kind
+pub fn error_histogram<T, I>(input: &str) -> HashMap<String, usize>
+where T: Send + FromStr + ObjFloat, I: Send + FromStr{
+    let labels: Vec<String> = parse_file::<T, I>(input).filter_map(|res| match res{
+        LineResult::Error(message) => Some(match message.split_once(", found "){
+            Some((label, _)) => label.to_string(),
+            None => message
+        }),
+        _ => None
+    }).collect();
+    let mut histogram = HashMap::new();
+    for label in labels { *histogram.entry(label).or_insert(0) += 1; }
+    histogram
+}
+
+// How many of each element a file has, as counted by count_elements
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ElementCounts{
+    pub positions: usize,
+    pub texcoords: usize,
+    pub normals: usize,
+    pub faces: usize
+}
+
+impl std::ops::Add for ElementCounts{
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self{ positions: self.positions + other.positions, texcoords: self.texcoords + other.texcoords, normals: self.normals + other.normals, faces: self.faces + other.faces }
+    }
+}
+
+// What's in a file, as given by summarize
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Summary{
+    pub positions: usize,
+    pub texcoords: usize,
+    pub normals: usize,
+    pub faces: usize,
+    pub triangles: usize, // After splitting the faces into triangles (see Face::triangles)
+    pub errors: usize // Lines that didn't parse
+}
+
+impl std::ops::Add for Summary{
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self{
+            positions: self.positions + other.positions, texcoords: self.texcoords + other.texcoords, normals: self.normals + other.normals,
+            faces: self.faces + other.faces, triangles: self.triangles + other.triangles, errors: self.errors + other.errors
+        }
+    }
+}
+
+// Parses the input and counts what's in it in one go, without collecting the lines, unlike count_elements the lines are checked
+// Numbers are parsed as f64 and i64, so only values that don't fit those count as errors
+pub fn summarize(input: &str) -> Summary{
+    parse_file::<f64, i64>(input)
+    .map(|line|{
+        let mut summary = Summary::default();
+        match line{
+            LineResult::VertDataLine(VertexData::Coord2{ .. } | VertexData::Coord3{ .. } | VertexData::Coord4{ .. }) => summary.positions = 1,
+            LineResult::VertDataLine(VertexData::Normal{ .. }) => summary.normals = 1,
+            LineResult::VertDataLine(_) => summary.texcoords = 1,
+            LineResult::FaceLine(face) => (summary.faces, summary.triangles) = (1, face.len() - 2),
+            LineResult::Error(_) => summary.errors = 1,
+            _ => {}
+        }
+        summary
+    })
+    .reduce(Summary::default, |a, b| a + b)
+}
+
+// Quickly counts elements by only looking at the first word of each line, so it doesn't check that the lines are valid
+// Lines are found with memchr, in chunks of about COUNT_CHUNK_LEN bytes that are counted in parallel
+pub fn count_elements(input: &str) -> ElementCounts{
+    newline_chunks(input.as_bytes()).into_par_iter()
+    .map(|chunk|{
+        let mut counts = ElementCounts::default();
+        for line in split_lines(chunk){
+            match first_word(line){
+                b"v" => counts.positions += 1,
+                b"vt" => counts.texcoords += 1,
+                b"vn" => counts.normals += 1,
+                b"f" => counts.faces += 1,
+                _ => {}
+            }
+        }
+        counts
+    })
+    .reduce(ElementCounts::default, |a, b| a + b)
+}
+
+// The kind of every line (as it would come out of parse_file) by only looking at its first word, for a quick overview of a file
+// Nothing past the first word is parsed, so a line that wouldn't parse still gets the kind its statement has, unknown statements are Error
+pub fn classify_lines(input: &str) -> Vec<LineKind>{
+    input.par_split('\n').map(statement_kind).collect()
+}
+
+// What kind of line the first word of line makes it, Error if it's not a statement this crate knows
+#[inline]
+fn statement_kind(line: &str) -> LineKind{
+    let word = first_word(line.as_bytes());
+    match &word[..word.iter().position(|&b| b == b'#' || b == b'\r').unwrap_or(word.len())]{
+        b"" => LineKind::NoData,
+        b"v" | b"vt" | b"vn" => LineKind::VertData,
+        b"f" => LineKind::Face,
+        b"g" => LineKind::Group,
+        b"o" => LineKind::Object,
+        b"usemtl" | b"mtllib" => LineKind::Material,
+        b"usemap" | b"maplib" => LineKind::TextureMap,
+        b"cstype" | b"deg" | b"curv" => LineKind::FreeForm,
+        _ => LineKind::Error
+    }
+}
+
+// Number of lines in the input, the same as input.split('\n').count() (so a trailing '\n' is followed by an empty line)
+pub fn line_count(input: &str) -> usize{
+    memchr::memchr_iter(b'\n', input.as_bytes()).count() + 1
+}
+
+const COUNT_CHUNK_LEN: usize = 64 * 1024;
+
+// Splits the input right after a '\n' every COUNT_CHUNK_LEN bytes or so, so no line is split between chunks
+fn newline_chunks(mut bytes: &[u8]) -> Vec<&[u8]>{
+    let mut chunks = Vec::with_capacity(bytes.len() / COUNT_CHUNK_LEN + 1);
+    while bytes.len() > COUNT_CHUNK_LEN{
+        let Some(i) = memchr::memchr(b'\n', &bytes[COUNT_CHUNK_LEN..]) else { break; };
+        let (chunk, rest) = bytes.split_at(COUNT_CHUNK_LEN + i + 1);
+        chunks.push(chunk);
+        bytes = rest;
+    }
+    chunks.push(bytes);
+    chunks
+}
+
+// Like split('\n'), but with memchr
+#[inline]
+fn split_lines(bytes: &[u8]) -> impl Iterator<Item = &[u8]>{
+    let mut start = 0;
+    memchr::memchr_iter(b'\n', bytes).map(Some).chain(std::iter::once(None)).map(move |end|{
+        let end = end.unwrap_or(bytes.len());
+        let line = &bytes[start..end];
+        start = end + 1;
+        line
+    })
+}
+
+// The first word of a line, ignoring leading whitespace, words end at a space or tab
+#[inline]
+fn first_word(line: &[u8]) -> &[u8]{
+    let start = line.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(line.len());
+    let line = &line[start..];
+    &line[..line.iter().position(|&b| b == b' ' || b == b'\t').unwrap_or(line.len())]
+}
+
+// Only the v lines of the input, for a quick bounding box or vertex count, malformed ones are skipped
+// Other lines are rejected by looking at their first bytes, without going through the parsers
+// There's nowhere to put an error, so input that doesn't look like text (see looks_like_text) gives no positions at all
+pub fn parse_positions_only<'input, T>(input: &'input str) -> impl ParallelIterator<Item = VertexData<T>> + 'input
+where T: Send + FromStr + ObjFloat + 'input{
+    if !looks_like_text(input) { return Either::Right(rayon::iter::empty()); }
+    let options = ParseOptions::default();
+    Either::Left(
+        input.par_split('\n')
+        .filter(|line| matches!(line.trim_start().as_bytes(), [b'v', b' ' | b'\t', ..]))
+        .filter_map(move |line|{
+            let (_, v) = alt((
+                terminated(|i| parse_coord3(i, &options), end_line),
+                terminated(|i| parse_coord2(i, &options), end_line),
+                terminated(|i| parse_coord4(i, &options), end_line),
+            ))(line).ok()?;
+            Some(v)
+        })
+    )
+}
+
+// Only the f lines of the input, fanned into triangles (see Face::triangles), for filling an index buffer directly
+// Like parse_positions_only, other lines are rejected by their first bytes, malformed faces are skipped and input that isn't text gives nothing
+pub fn parse_file_triangulated<'input, I>(input: &'input str) -> impl ParallelIterator<Item = [VertexIndeces<I>; 3]> + 'input
+where I: Send + Copy + FromStr + 'input{
+    if !looks_like_text(input) { return Either::Right(rayon::iter::empty()); }
+    let options = ParseOptions::default();
+    Either::Left(
+        input.par_split('\n')
+        .filter(|line| matches!(line.trim_start().as_bytes(), [b'f', b' ' | b'\t', ..]))
+        .flat_map_iter(move |line|{
+            let face = terminated(|i| parse_facen::<I>(i, &options), end_line)(line).ok().map(|(_, face)| face);
+            // Same triangles as Face::triangles, but owning the face so they don't have to be collected first
+            face.into_iter().flat_map(|face| fan_triangles(face.len()).filter_map(move |[a, b, c]| Some([*face.vertex(a)?, *face.vertex(b)?, *face.vertex(c)?])))
+        })
+    )
+}
+
+#[inline]
+pub(crate) fn line_result<'a, T, I>(line: &'a str, options: &ParseOptions) -> LineResult<'a, T, I>
+where T: FromStr + ObjFloat, I: FromStr{
+    parse_line_with_options(line, options)
+    .map(|(_unconsumed, parsed)| parsed)
+    .unwrap_or_else(|_|{
+        let too_long = options.max_line_len.is_some_and(|max| line.len() > max);
+        if !options.unknown_as_error && !too_long && statement_kind(line) == LineKind::Error { LineResult::NoData }
+        else { LineResult::Error(describe_error::<T, I>(line, options)) }
+    })
+}
+
+// Works out what's wrong with a line that didn't parse, for a message like "expected float, found '#'"
+// Goes over the words of the line again, since the nom error only tells which of the alternatives failed last
+fn describe_error<T, I>(line: &str, options: &ParseOptions) -> String
+where T: FromStr + ObjFloat, I: FromStr{
+    use nom::error::ErrorKind;
+    if options.max_line_len.is_some_and(|max| line.len() > max) {
+        return String::from("line is too long");
+    }
+    let (content, comment) = match line.find('#') { Some(i) => (&line[..i], true), None => (line, false) };
+    let mut words = content.split_whitespace();
+    let Some(statement) = words.next() else { return String::from("could not parse line"); };
+    let words: Vec<&str> = words.collect();
+    let found = |n: usize| match words.get(n) { Some(word) => format!("'{}'", word), None if comment => String::from("'#'"), None => String::from("end of line") };
+    let whole = |res: IResult<&str, ()>| match res { Ok(("", ())) => Ok(()), Ok(_) => Err(None), Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(Some(e.code)), Err(_) => Err(None) };
+    // min..=max numbers of the given kind, each checked with check
+    let numbers = |what: &str, min: usize, max: usize, check: &dyn Fn(&str) -> Result<(), Option<ErrorKind>>|{
+        for n in 0..max{
+            let Some(word) = words.get(n) else {
+                return if n < min { format!("expected {}, found {}", what, found(n)) } else { String::from("could not parse line") };
+            };
+            match check(word){
+                Ok(()) => {},
+                Err(Some(ErrorKind::TooLarge)) => return format!("number too large, found '{}'", word),
+                Err(Some(ErrorKind::Satisfy)) => return format!("negative index not allowed with an unsigned index type, found '{}'", word),
+                Err(_) => return format!("expected {}, found '{}'", what, word)
+            }
+        }
+        if words.len() > max { format!("expected end of line, found {}", found(max)) } else { String::from("could not parse line") }
+    };
+    let float = |word: &str| whole(map(|i| parse_float::<T>(i, options), |_| ())(word));
+    match statement{
+        "v" => numbers("float", if options.strict { 3 } else { 2 }, 4, &float),
+        "vn" => numbers("float", 3, 3, &float),
+        "vt" => numbers("float", 1, 3, &float),
+        "f" => {
+            let vertex = |word: &str| whole(map(|i| parse_face_vertex::<I>(i, options), |_| ())(word));
+            match words.iter().find(|word| vertex(word).is_err()){
+                Some(word) if word.starts_with('/') => format!("missing coordinate index, found '{}'", word), // The coord index is the only one that's required
+                _ => numbers("face vertex", 3, usize::MAX, &vertex)
+            }
+        },
+        "g" | "o" | "usemtl" | "mtllib" | "usemap" | "maplib" if words.is_empty() => format!("expected name, found {}", found(0)),
+        "usemap" => format!("expected end of line, found {}", found(1)),
+        "cstype" | "deg" | "curv" => format!("malformed '{}' statement", statement),
+        _ => format!("unknown statement '{}'", statement)
+    }
+}
+
+
+pub fn parse_line<T, I>(input: &str) -> IResult<&str, LineResult<'_, T, I>>
+where T: FromStr + ObjFloat, I: FromStr{
+    parse_line_with_options(input, &ParseOptions::default())
+}
+
+/// Parses a single face vertex (v, v/vt, v//vn or v/vt/vn), for reusing the grammar of face lines in other formats
+/// Stops at the end of the vertex, whatever comes after it is left for the caller
+///
+/// ```
+/// use objld::{parse_vertex_indices, VertexIndeces};
+///
+/// let (rest, v) = parse_vertex_indices::<i32>("1/2/3 4/5/6").unwrap();
+/// assert_eq!(v, VertexIndeces{ coord_rindex: 1, texcoord_rindex: Some(2), normal_rindex: Some(3) });
+/// assert_eq!(rest, " 4/5/6");
+/// let (_, v) = parse_vertex_indices::<i32>("7//-1").unwrap();
+/// assert_eq!((v.texcoord_rindex, v.normal_rindex), (None, Some(-1)));
+/// ```
+pub fn parse_vertex_indices<I>(input: &str) -> IResult<&str, VertexIndeces<I>>
+where I: FromStr{
+    parse_face_vertex(input, &ParseOptions::default())
+}
+
+// Like parse_line, but instead of failing on a line with extra text after an element, returns the element and that text
+// (comments and trailing whitespace aren't extra), lines that don't start with an element give an Error and the whole line
+pub fn parse_line_remainder<T, I>(input: &str) -> (LineResult<'_, T, I>, &str)
+where T: FromStr + ObjFloat, I: FromStr{
+    let options = ParseOptions::default();
+    if let Ok((rest, res)) = parse_line_with_options(input, &options){
+        return (res, rest);
+    }
+    use LineResult::VertDataLine;
+    // Longest forms first, since nothing has to come after them anymore
+    let res = alt((
+        map(|i| parse_coord4(i, &options), VertDataLine),
+        map(|i| parse_coord3(i, &options), VertDataLine),
+        map(|i| parse_coord2(i, &options), VertDataLine),
+        map(|i| parse_normal(i, &options), VertDataLine),
+        map(|i| parse_texcoord3(i, &options), VertDataLine),
+        map(|i| parse_texcoord2(i, &options), VertDataLine),
+        map(|i| parse_texcoord1(i, &options), VertDataLine),
+        map(|i| parse_facen(i, &options), LineResult::FaceLine),
+        map(parse_group, |g| LineResult::Group(Cow::Borrowed(g))),
+        map(parse_object, |o| LineResult::Object(Cow::Borrowed(o))),
+        map(parse_material, LineResult::Material),
+        map(parse_texture_map_ref, LineResult::TextureMap),
+        map(|i| parse_freeform(i, &options), LineResult::FreeFormLine),
+    ))(input);
+    match res{
+        Ok((rest, res)) => (res, rest),
+        Err(_) => (LineResult::Error(describe_error::<T, I>(input, &options)), input)
+    }
+}
+
+pub fn parse_line_with_options<'a, T, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, LineResult<'a, T, I>>
+where T: FromStr + ObjFloat, I: FromStr{
+    if options.max_line_len.is_some_and(|max| input.len() > max) {
+        // The error doesn't point into the line so it can't end up copied into the message
+        return Err(nom::Err::Error(nom::error::Error::new(&input[..0], nom::error::ErrorKind::TooLarge)));
+    }
     use LineResult::VertDataLine;
     use LineResult::FaceLine;
     use LineResult::NoData;
+    use LineResult::Group;
+    use LineResult::Object;
+    use LineResult::Material;
+    use LineResult::TextureMap;
+    use LineResult::FreeFormLine;
     alt((
         map(end_line, |_| NoData), // If the line doesn't contain anything just return None
 
         // 1 float
-        map(tuple((parse_texcoord1, end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_texcoord1(i, options), end_line)), |(v, _)| VertDataLine(v) ),
 
         // 2 floats
-        map(tuple((parse_coord2, end_line)), |(v, _)| VertDataLine(v) ),
-        map(tuple((parse_texcoord2, end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_coord2(i, options), end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_texcoord2(i, options), end_line)), |(v, _)| VertDataLine(v) ),
 
         // 3 floats
-        map(tuple((parse_coord3, end_line)), |(v, _)| VertDataLine(v) ),
-        map(tuple((parse_normal, end_line)), |(v, _)| VertDataLine(v) ),
-        map(tuple((parse_texcoord3, end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_coord3(i, options), end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_normal(i, options), end_line)), |(v, _)| VertDataLine(v) ),
+        map(tuple((|i| parse_texcoord3(i, options), end_line)), |(v, _)| VertDataLine(v) ),
+
+        // 4 floats
+        map(tuple((|i| parse_coord4(i, options), end_line)), |(v, _)| VertDataLine(v) ),
 
         // 3 fields
-        map(tuple((parse_face3, end_line)), |(f, _)| FaceLine(f)),
+        map(tuple((|i| parse_face3(i, options), end_line)), |(f, _)| FaceLine(f)),
 
         // 4 fields
-        map(tuple((parse_face4, end_line)), |(f, _)| FaceLine(f)),
+        map(tuple((|i| parse_face4(i, options), end_line)), |(f, _)| FaceLine(f)),
+
+        // 5 or more fields
+        map(tuple((|i| parse_facen(i, options), end_line)), |(f, _)| FaceLine(f)),
+
+        // Context
+        alt((
+            map(tuple((parse_group, end_line)), |(g, _)| Group(Cow::Borrowed(g))),
+            map(tuple((parse_object, end_line)), |(o, _)| Object(Cow::Borrowed(o))),
+            map(tuple((parse_material, end_line)), |(m, _)| Material(m)),
+            map(tuple((parse_texture_map_ref, end_line)), |(m, _)| TextureMap(m)),
+        )),
+
+        // Free-form
+        map(tuple((|i| parse_freeform(i, options), end_line)), |(f, _)| FreeFormLine(f)),
+
+        // Malformed, but common enough to deserve a clearer error than whatever the face parsers failed with
+        map(tuple((|i| parse_short_face(i, options), end_line)), |(n, _)| LineResult::Error(String::from(if n == 0 { "empty face" } else { "a face needs at least 3 vertices" }))),
     ))(input)
 
 }
@@ -160,92 +1946,262 @@ where T: FromStr, I: FromStr{
 /// Primitive parsers
 /**********************************************************************************/
 #[inline]
-fn consume_num(input: &str) -> IResult<&str, &str>{ recognize(tuple( ( opt(one_of("+-")), digit1, opt(char('.')), digit0, /*exp*/ opt(tuple((char('e'), one_of("+-"), digit1))) /*exp*/ ) ) )(input) }
+pub(crate) fn consume_num(input: &str) -> IResult<&str, &str>{ recognize(tuple( ( opt(one_of("+-")), digit1, opt(char('.')), digit0, /*exp*/ opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))) /*exp*/ ) ) )(input) }
 
+// Same as consume_num, but ',' is also accepted as the decimal point
 #[inline]
-fn parse_float<T>(input: &str) -> IResult<&str, T>
-where T: FromStr{
-    
+fn consume_comma_num(input: &str) -> IResult<&str, &str>{ recognize(tuple( ( opt(one_of("+-")), digit1, opt(one_of(".,")), digit0, /*exp*/ opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))) /*exp*/ ) ) )(input) }
+
+// Note: Errors with ErrorKind::Float if the number is malformed, and ErrorKind::TooLarge if it's a fine number that T can't represent (overflows to infinity)
+#[inline]
+pub(crate) fn parse_float<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, T>
+where T: FromStr + ObjFloat{
+    let (input, num) = if options.comma_decimal { consume_comma_num(input)? } else { consume_num(input)? };
+    let val = if options.comma_decimal && num.contains(',') { T::from_num(&num.replacen(',', ".", 1)) } else { T::from_num(num) };
+    let val: T = val.ok_or(nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::Float)))?;
+    if val.is_infinite() {
+        return Err(nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::TooLarge)));
+    }
+    Ok((input, val))
+}
+
+// The fast path parse_float takes for f32 and f64 with the fast-float feature, exposed for parsing numbers outside of lines
+// Note: Doesn't accept ',' as the decimal point or report overflow like parse_float does
+#[cfg(feature = "fast-float")]
+pub fn parse_float_fast<T>(input: &str) -> IResult<&str, T>
+where T: lexical_core::FromLexical{
     let (input, num) = consume_num(input)?;
-    let val: T = T::from_str(num).map_err(|_| nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::Float)))?;
-    return Ok((input, val));
+    let val: T = lexical_core::parse(num.as_bytes()).map_err(|_| nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::Float)))?;
+    Ok((input, val))
 }
 
+// Note: Errors with ErrorKind::Digit if the number is malformed (not an integer), ErrorKind::Satisfy if it's negative and T is unsigned
+// (can't be negative at all), and ErrorKind::TooLarge if it's an integer that T can't represent otherwise
 #[inline]
 fn parse_num<T>(input: &str) -> IResult<&str, T>
 where T: FromStr{
     let (input, num) = consume_num(input)?;
-    let val: T = str::parse(num).map_err(|_| nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::Digit)))?;
-    return Ok((input, val));
+    let val: T = str::parse(num).map_err(|_|{
+        let integer = num.trim_start_matches(['+', '-']).bytes().all(|b| b.is_ascii_digit());
+        let kind = if !integer { nom::error::ErrorKind::Digit }
+            else if num.starts_with('-') && str::parse::<T>("-1").is_err() { nom::error::ErrorKind::Satisfy }
+            else { nom::error::ErrorKind::TooLarge };
+        nom::Err::Error(nom::error::Error::new(num, kind))
+    })?;
+    Ok((input, val))
+}
+
+// Like parse_num, but rejects -0 (ErrorKind::Verify), relative indices start at -1 and since 0 is already not a valid index -0 is never meaningful
+// Note: 0 itself is still accepted here (it can't be resolved, see resolve_index)
+#[inline]
+fn parse_index<I>(input: &str) -> IResult<&str, I>
+where I: FromStr{
+    let (rest, num) = consume_num(input)?;
+    if num.starts_with('-') && num[1..].bytes().all(|b| b == b'0') {
+        return Err(nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::Verify)));
+    }
+    let (_, val) = parse_num(num)?;
+    Ok((rest, val))
+}
+
+// Like parse_index, but positive indices count from index_base and are shifted to count from 1 (see ParseOptions::index_base)
+// Ones below index_base become 0, which never resolves, relative ones don't depend on the base so they're left alone
+fn parse_index_with_base<I>(input: &str, index_base: u32) -> IResult<&str, I>
+where I: FromStr{
+    let (rest, num) = consume_num(input)?;
+    let Some(index) = num.strip_prefix('+').unwrap_or(num).parse::<u64>().ok().filter(|_| index_base != 1) else { return parse_index(input); };
+    let shifted = (index + 1).saturating_sub(u64::from(index_base));
+    let val = shifted.to_string().parse().map_err(|_| nom::Err::Error(nom::error::Error::new(num, nom::error::ErrorKind::TooLarge)))?;
+    Ok((rest, val))
+}
+
+#[inline]
+pub(crate) fn parse_name(input: &str) -> IResult<&str, &str>{ is_not(" \t\r\n#")(input) }
+
+// A name that takes up the rest of the line, taken verbatim (spaces, quotes and all) up to a comment, without trailing whitespace
+#[inline]
+fn parse_line_name(input: &str) -> IResult<&str, &str>{
+    let (_, text) = is_not("\r\n#")(input)?;
+    let name = text.trim_end_matches([' ', '\t']);
+    if name.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::IsNot)));
+    }
+    Ok((&input[name.len()..], name))
 }
 
+// Yields the text of the comment (if any) without the '#' and surrounding whitespace, a trailing \r from CRLF included
 #[inline]
-fn end_line(input: &str) -> IResult<&str, &str>{
+pub(crate) fn end_line(input: &str) -> IResult<&str, Option<&str>>{
     type Comment<'a> = &'a str;
-   fn consume_comment(input: &str) -> IResult<&str, Comment> { recognize(tuple((space0, char('#'), rest)))(input) }
-   recognize(tuple((  space0, opt(consume_comment), many0(one_of("\r\n ")), eof  )))(input)
+   fn consume_comment(input: &str) -> IResult<&str, Comment<'_>> { map(tuple((space0, char('#'), rest)), |data: (_, _, &str)| data.2.trim())(input) }
+   map(tuple((  space0, opt(consume_comment), many0(one_of("\r\n ")), eof  )), |data| data.1)(input)
 }
 /**********************************************************************************/
 
-// For 2d vertex coords
-fn parse_coord2<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr {
-    let (input, data) = tuple(( space0, tag("v"), space1, parse_float, space1, parse_float ))(input)?;
+// For 2d vertex coords, which aren't in the spec so they're rejected with options.strict
+fn parse_coord2<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    if options.strict {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify)));
+    }
+    let float = |i| parse_float(i, options);
+    let (input, data) = tuple(( space0, tag("v"), space1, float, space1, float ))(input)?;
     Ok((input, VertexData::Coord2{x: data.3, y: data.5}))
 }
 
 // For 3d vertex coords
-fn parse_coord3<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr {
-    let (input, data) = tuple(( space0, tag("v"), space1, parse_float, space1, parse_float, space1, parse_float ))(input)?;
+fn parse_coord3<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| parse_float(i, options);
+    let (input, data) = tuple(( space0, tag("v"), space1, float, space1, float, space1, float ))(input)?;
     Ok((input, VertexData::Coord3{x: data.3, y: data.5, z: data.7}))
 }
 
+// For 3d vertex coords with a weight
+fn parse_coord4<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| parse_float(i, options);
+    let (input, data) = tuple(( space0, tag("v"), space1, float, space1, float, space1, float, space1, float ))(input)?;
+    Ok((input, VertexData::Coord4{x: data.3, y: data.5, z: data.7, w: data.9}))
+}
+
 // For 3d normals (no support for 2d normals)
-fn parse_normal<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr {
-    let (input, data) = tuple(( space0, tag("vn"), space1, parse_float, space1, parse_float, space1, parse_float ))(input)?;
+fn parse_normal<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| parse_float(i, options);
+    let (input, data) = tuple(( space0, tag("vn"), space1, float, space1, float, space1, float ))(input)?;
     Ok((input, VertexData::Normal{x: data.3, y: data.5, z: data.7}))
 }
 
 // For 1D textures
-fn parse_texcoord1<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr {
-    let (input, data) = tuple(( space0, tag("vt"), space1, parse_float ))(input)?;
+fn parse_texcoord1<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| map(|i| parse_float(i, options), |f| wrap_texcoord(f, options.texcoord_wrap))(i);
+    let (input, data) = tuple(( space0, tag("vt"), space1, float ))(input)?;
     Ok((input, VertexData::TextureCoord1{u: data.3}))
 }
 
 // For 2D textures
-fn parse_texcoord2<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr {
-    let (input, data) = tuple(( space0, tag("vt"), space1, parse_float, space1, parse_float ))(input)?;
+fn parse_texcoord2<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| map(|i| parse_float(i, options), |f| wrap_texcoord(f, options.texcoord_wrap))(i);
+    let (input, data) = tuple(( space0, tag("vt"), space1, float, space1, float ))(input)?;
     Ok((input, VertexData::TextureCoord2{u: data.3, v: data.5}))
 }
 
 // For 3D textures
-fn parse_texcoord3<T>(input: &str) -> IResult<&str, VertexData<T>>
-where T: FromStr{
-    let (input, data) = tuple(( space0, tag("vt"), space1, parse_float, space1, parse_float, space1, parse_float ))(input)?;
+fn parse_texcoord3<'a, T>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexData<T>>
+where T: FromStr + ObjFloat{
+    let float = |i| map(|i| parse_float(i, options), |f| wrap_texcoord(f, options.texcoord_wrap))(i);
+    let (input, data) = tuple(( space0, tag("vt"), space1, float, space1, float, space1, float ))(input)?;
     Ok((input, VertexData::TextureCoord3{u: data.3, v: data.5, w: data.7}))
 }
 
 // For face3 and face4
-fn parse_face_vertex<I>(input: &str) -> IResult<&str, VertexIndeces<I>>
+fn parse_face_vertex<'a, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, VertexIndeces<I>>
 where I: FromStr {
-    let (input, data) = tuple(( parse_num, char('/'), opt(parse_num), char('/'), opt(parse_num) ))(input)?; // NUM/OPT(NUM)/OPT(NUM)
-    Ok((input, VertexIndeces{  coord_rindex: data.0, texcoord_rindex: data.2, normal_rindex: data.4 }))
+    let slash = |i: &'a str| if options.lenient_slashes { delimited(space0, char('/'), space0)(i) } else { char('/')(i) };
+    let index = |i| parse_index_with_base(i, options.index_base);
+    let (input, data) = tuple(( index, opt(tuple(( slash, opt(index), opt(tuple(( slash, opt(index) ))) ))) ))(input)?; // NUM[/OPT(NUM)[/OPT(NUM)]]
+    let (texcoord_rindex, normal_rindex) = match data.1 {
+        Some((_, tex, norm)) => (tex, norm.and_then(|(_, norm)| norm)),
+        None => (None, None)
+    };
+    Ok((input, VertexIndeces{  coord_rindex: data.0, texcoord_rindex, normal_rindex }))
 }
 
 // For triangle faces
-fn parse_face3<I>(input: &str) -> IResult<&str, Face<I>>
+fn parse_face3<'a, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Face<I>>
 where I: FromStr {
-    let (input, data) = tuple(( space0, tag("f"), space1, parse_face_vertex, space1, parse_face_vertex, space1, parse_face_vertex ))(input)?;
+    let vertex = |i| parse_face_vertex(i, options);
+    let (input, data) = tuple(( space0, tag("f"), space1, vertex, space1, vertex, space1, vertex ))(input)?;
     Ok((input, Face::Face3{ v1: data.3, v2: data.5, v3: data.7 })) // Intentionally ignore data.9
 }
 
 // For square faces
-fn parse_face4<I>(input: &str) -> IResult<&str, Face<I>>
+fn parse_face4<'a, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Face<I>>
 where I: FromStr {
-    let (input, data) = tuple(( space0, tag("f"), space1, parse_face_vertex, space1, parse_face_vertex, space1, parse_face_vertex, space1, parse_face_vertex ))(input)?;
+    let vertex = |i| parse_face_vertex(i, options);
+    let (input, data) = tuple(( space0, tag("f"), space1, vertex, space1, vertex, space1, vertex, space1, vertex ))(input)?;
     Ok((input, Face::Face4{ v1: data.3, v2: data.5, v3: data.7, v4: data.9 }))
 }
+
+// For polygon faces (any number of vertices, but parse_face3 and parse_face4 should be tried first)
+fn parse_facen<'a, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Face<I>>
+where I: FromStr {
+    // fold_many1 pushes one vertex at a time, reserving for every word up to a comment first keeps long faces from reallocating
+    let reserve = || FaceVertices::with_capacity(input.split('#').next().unwrap_or("").split_ascii_whitespace().count().saturating_sub(1));
+    // With options.skip_bad_vertices a word that isn't a whole vertex is dropped instead of ending the face
+    let vertex = |i: &'a str|{
+        let word_end = peek(alt(( space1, eof, tag("#"), tag("\r"), tag("\n") )));
+        if options.skip_bad_vertices { alt(( map(terminated(|i| parse_face_vertex(i, options), word_end), Some), map(is_not(" \t\r\n#"), |_| None) ))(i) }
+        else { map(|i| parse_face_vertex(i, options), Some)(i) }
+    };
+    let vertices = fold_many1(preceded(space1, vertex), reserve, |mut verts, v|{ verts.extend(v); verts });
+    let (input, data) = tuple(( space0, tag("f"), verify(vertices, |verts: &FaceVertices<I>| verts.len() >= 3) ))(input)?;
+    // Only 5 or more vertices unless something else went first or vertices were skipped, give the same variants parse_face3/4 would
+    let face = match data.2{
+        verts if verts.len() <= 4 => {
+            let mut verts = verts.into_iter();
+            let (v1, v2, v3) = (verts.next().unwrap(), verts.next().unwrap(), verts.next().unwrap());
+            match verts.next(){
+                Some(v4) => Face::Face4{ v1, v2, v3, v4 },
+                None => Face::Face3{ v1, v2, v3 }
+            }
+        },
+        verts => Face::FaceN{ verts }
+    };
+    Ok((input, face))
+}
+
+// For faces with less than 3 vertices, which aren't valid, gives how many they have (0 for a lone f)
+fn parse_short_face<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, usize>{
+    let (input, data) = tuple(( space0, tag("f"), many_m_n(0, 2, preceded(space1, |i| parse_face_vertex::<i64>(i, options))) ))(input)?;
+    Ok((input, data.2.len()))
+}
+
+// For groups, the name is the rest of the line (see parse_line_name), so g a b is one group called "a b"
+fn parse_group(input: &str) -> IResult<&str, &str>{
+    let (input, data) = tuple(( space0, tag("g"), space1, parse_line_name ))(input)?;
+    Ok((input, data.3))
+}
+
+// For objects, the name is the rest of the line like for groups
+fn parse_object(input: &str) -> IResult<&str, &str>{
+    let (input, data) = tuple(( space0, tag("o"), space1, parse_line_name ))(input)?;
+    Ok((input, data.3))
+}
+
+// For usemtl and mtllib, a usemtl takes the rest of the line as its name but mtllib has a list of file names
+fn parse_material(input: &str) -> IResult<&str, MaterialRef<'_>>{
+    alt((
+        map(tuple(( space0, tag("usemtl"), opt(preceded(space1, parse_line_name)) )), |data| MaterialRef::UseMtl(data.2.filter(|name| *name != "(null)").map(Cow::Borrowed))),
+        map(tuple(( space0, tag("mtllib"), space1, separated_list1(space1, parse_name) )), |data| MaterialRef::MtlLib(data.3.into_iter().map(Cow::Borrowed).collect())),
+    ))(input)
+}
+
+// For usemap and maplib
+fn parse_texture_map_ref(input: &str) -> IResult<&str, TextureMapRef<'_>>{
+    alt((
+        map(tuple(( space0, tag("usemap"), space1, parse_name )), |data| TextureMapRef::UseMap(Cow::Borrowed(data.3))),
+        map(tuple(( space0, tag("maplib"), space1, separated_list1(space1, parse_name) )), |data| TextureMapRef::MapLib(data.3.into_iter().map(Cow::Borrowed).collect())),
+    ))(input)
+}
+
+// For cstype, deg and curv
+fn parse_freeform<'a, T, I>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, FreeForm<T, I>>
+where T: FromStr + ObjFloat, I: FromStr{
+    let float = |i| parse_float(i, options);
+    let curve_type = alt((
+        map(tag("bmatrix"), |_| CurveType::BMatrix),
+        map(tag("bezier"), |_| CurveType::Bezier),
+        map(tag("bspline"), |_| CurveType::BSpline),
+        map(tag("cardinal"), |_| CurveType::Cardinal),
+        map(tag("taylor"), |_| CurveType::Taylor),
+    ));
+    alt((
+        map(tuple(( space0, tag("cstype"), space1, opt(tuple((tag("rat"), space1))), curve_type )), |data| FreeForm::CsType{ rational: data.3.is_some(), kind: data.4 }),
+        map(tuple(( space0, tag("deg"), space1, parse_num, opt(tuple((space1, parse_num))) )), |data| FreeForm::Degree{ u: data.3, v: data.4.map(|(_, v)| v) }),
+        map(tuple(( space0, tag("curv"), space1, float, space1, float, space1, separated_list1(space1, |i| parse_index_with_base(i, options.index_base)) )), |data| FreeForm::Curve{ u0: data.3, u1: data.5, control_points: data.7 }),
+    ))(input)
+}
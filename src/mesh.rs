@@ -0,0 +1,1166 @@
+use std::{collections::{HashMap, hash_map::Entry}, str::FromStr};
+
+use rayon::iter::ParallelIterator;
+
+use crate::{ElementCounts, Face, FanTriangles, LineResult, MeshSink, ObjFloat, ParseError, ParseOptions, VertexData, VertexIndeces, absolute_index, drive, fan_triangles, line_result, parse_file, resolve_index};
+
+// All the geometry of a .obj file, positive face indices are kept as they were in the file, negative (relative) ones are made
+// absolute when the face is added (see the MeshSink impl), so elements that come after a face don't change what it refers to
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mesh<T, I>{
+    pub positions: Vec<[T; 3]>,
+    pub texcoords: Vec<[T; 3]>, // Always (u, v, w), see the MeshSink impl
+    pub normals: Vec<[T; 3]>,
+    pub tangents: Vec<[T; 4]>, // One per normal, w is the handedness of the bitangent (see generate_tangents)
+    pub faces: Vec<Face<I>>
+}
+
+impl<T, I> Default for Mesh<T, I>{
+    fn default() -> Self {
+        Self{ positions: Vec::new(), texcoords: Vec::new(), normals: Vec::new(), tangents: Vec::new(), faces: Vec::new() }
+    }
+}
+
+impl<T, I> Mesh<T, I>{
+    pub fn is_empty(&self) -> bool{
+        self.positions.is_empty() && self.texcoords.is_empty() && self.normals.is_empty() && self.faces.is_empty()
+    }
+}
+
+impl<T, I> Mesh<T, I>
+where T: Copy + std::ops::Neg<Output = T>{
+    // Negates every normal, for meshes whose normals point inwards, the w of the tangents is negated too so the bitangents stay put
+    // Doesn't touch the faces, if their winding is inside-out as well use reverse_winding
+    pub fn flip_normals(&mut self){
+        for n in &mut self.normals { *n = n.map(|c| -c); }
+        for t in &mut self.tangents { t[3] = -t[3]; }
+    }
+}
+
+impl<T, I> Mesh<T, I>{
+    // Reverses the winding of every face (see Face::reverse_winding), so front faces become back faces
+    pub fn reverse_winding(&mut self){
+        for face in &mut self.faces { face.reverse_winding(); }
+    }
+}
+
+// A face vertex with its indices looked up in the mesh
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ResolvedVertex<T>{
+    pub position: [T; 3],
+    pub texcoord: Option<[T; 3]>,
+    pub normal: Option<[T; 3]>
+}
+
+impl<T, I> Mesh<T, I>
+where T: Copy, I: Copy + TryInto<i64>{
+    // Returns None if any of the indices are out of range
+    pub fn resolve_vertex(&self, v: &VertexIndeces<I>) -> Option<ResolvedVertex<T>>{
+        let v = v.resolve(self.positions.len(), self.texcoords.len(), self.normals.len())?;
+        Some(ResolvedVertex{
+            position: self.positions[v.coord_rindex],
+            texcoord: v.texcoord_rindex.map(|i| self.texcoords[i]),
+            normal: v.normal_rindex.map(|i| self.normals[i]),
+        })
+    }
+
+    // Every face split into triangles (see Face::triangles), triangles with out of range indices are skipped
+    pub fn triangles(&self) -> Triangles<'_, T, I>{
+        Triangles{ mesh: self, faces: self.faces.iter(), fan: None }
+    }
+}
+
+// Every face along with the positions of its vertices, faces with out of range indices are skipped
+// Relative indices are resolved against the positions before the face, collect_mesh already made them absolute (see Mesh)
+pub fn faces_with_positions<T, I>(mesh: &Mesh<T, I>) -> impl Iterator<Item = (&Face<I>, Vec<[T; 3]>)>
+where T: Copy, I: Copy + TryInto<i64>{
+    mesh.faces.iter().filter_map(|face|{
+        let positions = face.vertices().map(|v| resolve_index(v.coord_rindex, mesh.positions.len()).map(|i| mesh.positions[i])).collect::<Option<Vec<_>>>()?;
+        Some((face, positions))
+    })
+}
+
+pub struct Triangles<'a, T, I>{
+    mesh: &'a Mesh<T, I>,
+    faces: std::slice::Iter<'a, Face<I>>,
+    fan: Option<(&'a Face<I>, FanTriangles)> // The face being split and its triangles that are left
+}
+
+impl<T, I> Iterator for Triangles<'_, T, I>
+where T: Copy, I: Copy + TryInto<i64>{
+    type Item = [ResolvedVertex<T>; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop{
+            let Some((face, fan)) = &mut self.fan else {
+                let face = self.faces.next()?;
+                self.fan = Some((face, fan_triangles(face.len())));
+                continue;
+            };
+            let Some([a, b, c]) = fan.next() else {
+                self.fan = None;
+                continue;
+            };
+            let vertex = |n| face.vertex(n).and_then(|v| self.mesh.resolve_vertex(v));
+            if let (Some(v1), Some(v2), Some(v3)) = (vertex(a), vertex(b), vertex(c)){
+                return Some([v1, v2, v3]);
+            }
+        }
+    }
+}
+
+impl<'a, T, I> IntoIterator for &'a Mesh<T, I>
+where T: Copy, I: Copy + TryInto<i64>{
+    type Item = [ResolvedVertex<T>; 3];
+    type IntoIter = Triangles<'a, T, I>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.triangles()
+    }
+}
+
+// Missing components (z of a Coord2, v and w of a TextureCoord1, w of a TextureCoord2) are filled in with T::default() (0 for floats),
+// so every tex coord is stored as (u, v, w) no matter how many components it had in the file
+// Relative indices are resolved against the elements added so far (like resolve_relative does), ones that point before the
+// first element become 0 so they never resolve, instead of resolving against elements that come later
+impl<T, I> MeshSink<T, I> for Mesh<T, I>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    fn add_position(&mut self, x: T, y: T, z: Option<T>){ self.positions.push([x, y, z.unwrap_or_default()]); }
+    fn add_normal(&mut self, x: T, y: T, z: T){ self.normals.push([x, y, z]); }
+    fn add_texcoord(&mut self, u: T, v: Option<T>, w: Option<T>){ self.texcoords.push([u, v.unwrap_or_default(), w.unwrap_or_default()]); }
+    fn add_face(&mut self, mut face: Face<I>){
+        let counts = [self.positions.len(), self.texcoords.len(), self.normals.len()];
+        let absolute = |rindex: I, count: usize|{
+            let i = absolute_index(rindex, count);
+            if i.try_into().is_ok_and(|i: i64| i < 0) { I::try_from(0).unwrap_or(i) } else { i }
+        };
+        for v in face.vertices_mut(){
+            v.coord_rindex = absolute(v.coord_rindex, counts[0]);
+            v.texcoord_rindex = v.texcoord_rindex.map(|i| absolute(i, counts[1]));
+            v.normal_rindex = v.normal_rindex.map(|i| absolute(i, counts[2]));
+        }
+        self.faces.push(face);
+    }
+}
+
+// For building a mesh a bit at a time, same as collect_mesh but adding to what's already there
+impl<'a, T, I> Extend<LineResult<'a, T, I>> for Mesh<T, I>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    fn extend<R: IntoIterator<Item = LineResult<'a, T, I>>>(&mut self, results: R){ drive(results, self); }
+}
+
+// See the MeshSink impl for how the elements are stored, a file mixing vt u and vt u v lines gets (u, 0, 0) for the vt u ones
+// (see collect_mesh_mixed_texcoords for rejecting those instead)
+pub fn collect_mesh<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>) -> Mesh<T, I>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    collect_mesh_with_capacity(results, ElementCounts::default())
+}
+
+// Same as collect_mesh, but reserves room for the given counts upfront (see count_elements)
+pub fn collect_mesh_with_capacity<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>, counts: ElementCounts) -> Mesh<T, I>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    let mut mesh = Mesh{
+        positions: Vec::with_capacity(counts.positions),
+        texcoords: Vec::with_capacity(counts.texcoords),
+        normals: Vec::with_capacity(counts.normals),
+        tangents: Vec::new(),
+        faces: Vec::with_capacity(counts.faces)
+    };
+    drive(results, &mut mesh);
+    mesh
+}
+
+// Same as collect_mesh, but with error_on_mixed tex coords with a different number of components than the first one
+// (vt u after vt u v, ...) are an error instead of being filled in with 0s, the line is counted from the start of the results
+pub fn collect_mesh_mixed_texcoords<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>, error_on_mixed: bool) -> Result<Mesh<T, I>, ParseError>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    let mut mesh = Mesh::default();
+    let mut first_arity = None;
+    for (n, line) in results.into_iter().enumerate(){
+        let arity = match &line{
+            LineResult::VertDataLine(VertexData::TextureCoord1{ .. }) => Some(1),
+            LineResult::VertDataLine(VertexData::TextureCoord2{ .. }) => Some(2),
+            LineResult::VertDataLine(VertexData::TextureCoord3{ .. }) => Some(3),
+            _ => None
+        };
+        if let Some(arity) = arity.filter(|_| error_on_mixed){
+            let first = *first_arity.get_or_insert(arity);
+            if arity != first {
+                return Err(ParseError{ line: n + 1, message: format!("expected {} tex coord components like the first vt, found {}", first, arity) });
+            }
+        }
+        drive(std::iter::once(line), &mut mesh);
+    }
+    Ok(mesh)
+}
+
+// Same as collect_mesh, but the face indices are stored 0-based, ready for GPU APIs, negative ones are resolved against the
+// elements before the face (like the spec says), faces with indices that don't resolve (0 or out of range) are left out
+pub fn collect_mesh_zero_based<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>) -> Mesh<T, usize>
+where T: Default, I: Copy + TryInto<i64>{
+    let mut mesh = Mesh::default();
+    drive(results, &mut ZeroBased(&mut mesh));
+    mesh
+}
+
+struct ZeroBased<'m, T>(&'m mut Mesh<T, usize>);
+
+impl<T, I> MeshSink<T, I> for ZeroBased<'_, T>
+where T: Default, I: Copy + TryInto<i64>{
+    fn add_position(&mut self, x: T, y: T, z: Option<T>){ MeshSink::<T, usize>::add_position(self.0, x, y, z); }
+    fn add_normal(&mut self, x: T, y: T, z: T){ MeshSink::<T, usize>::add_normal(self.0, x, y, z); }
+    fn add_texcoord(&mut self, u: T, v: Option<T>, w: Option<T>){ MeshSink::<T, usize>::add_texcoord(self.0, u, v, w); }
+    fn add_face(&mut self, face: Face<I>){
+        let counts = (self.0.positions.len(), self.0.texcoords.len(), self.0.normals.len());
+        if face.vertices().all(|v| v.resolve(counts.0, counts.1, counts.2).is_some()){
+            self.0.faces.push(face.map_vertices(|v| v.resolve(counts.0, counts.1, counts.2).unwrap())); // Checked above
+        }
+    }
+}
+
+// Splits the results into one mesh per object (o line), the elements before the first o line (if any) go in a mesh without a name
+// Positive indices are rebased so they count from the start of their object, negative (relative) ones are made absolute like
+// collect_mesh does, faces that refer to elements of previous objects get index 0 for those, which won't resolve
+pub fn split_objects<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>) -> Vec<(Option<String>, Mesh<T, I>)>
+where T: Default, I: Copy + TryInto<i64> + TryFrom<i64>{
+    let mut objects = Vec::new();
+    let mut name: Option<String> = None;
+    let mut mesh = Mesh::default();
+    let mut offsets = [0usize; 3]; // Positions, texcoords and normals in the objects before this one
+    for line in results{
+        match line{
+            LineResult::Object(next) => {
+                offsets = [offsets[0] + mesh.positions.len(), offsets[1] + mesh.texcoords.len(), offsets[2] + mesh.normals.len()];
+                if name.is_some() || !mesh.is_empty() { objects.push((name.take(), std::mem::take(&mut mesh))); }
+                name = Some(next.into_owned());
+            },
+            LineResult::FaceLine(mut face) => {
+                for v in face.vertices_mut(){
+                    v.coord_rindex = rebase_index(v.coord_rindex, offsets[0]);
+                    v.texcoord_rindex = v.texcoord_rindex.map(|i| rebase_index(i, offsets[1]));
+                    v.normal_rindex = v.normal_rindex.map(|i| rebase_index(i, offsets[2]));
+                }
+                mesh.add_face(face);
+            },
+            line => drive(std::iter::once(line), &mut mesh)
+        }
+    }
+    if name.is_some() || !mesh.is_empty() { objects.push((name, mesh)); }
+    objects
+}
+
+#[inline]
+fn rebase_index<I>(rindex: I, offset: usize) -> I
+where I: Copy + TryInto<i64> + TryFrom<i64>{
+    match rindex.try_into(){
+        Ok(i) if i > 0 => I::try_from(i64::try_from(offset).map_or(0, |offset| i.saturating_sub(offset).max(0))).unwrap_or(rindex),
+        _ => rindex
+    }
+}
+
+// Best-effort loading, builds the mesh out of every line that did parse and reports the ones that didn't
+pub fn parse_collecting_errors<T, I>(input: &str) -> (Mesh<T, I>, Vec<ParseError>)
+where T: Send + FromStr + Default + ObjFloat, I: Send + FromStr + Copy + TryInto<i64> + TryFrom<i64>{
+    let mut errors = Vec::new();
+    let results: Vec<LineResult<T, I>> = parse_file(input).collect();
+    let mesh = collect_mesh(results.into_iter().enumerate().filter_map(|(i, res)| match res{
+        LineResult::Error(message) => { errors.push(ParseError{ line: i + 1, message }); None },
+        res => Some(res)
+    }));
+    (mesh, errors)
+}
+
+// Only the first n faces of the input and the elements before the last of them, for a quick preview of a huge file
+// Goes through the lines one by one (not in parallel) and stops right after the nth face, so the rest of the input is never parsed
+pub fn parse_file_take_faces<T, I>(input: &str, n: usize) -> Mesh<T, I>
+where T: FromStr + Default + ObjFloat, I: FromStr + Copy + TryInto<i64> + TryFrom<i64>{
+    let mut mesh = Mesh::default();
+    let mut faces = 0;
+    drive(input.split('\n').map(|line| line_result(line, &ParseOptions::default())).take_while(|line|{
+        let more = faces < n;
+        if matches!(line, LineResult::FaceLine(_)) { faces += 1; }
+        more
+    }), &mut mesh);
+    mesh
+}
+
+// Fails with every error in the input if there are any
+impl<T, I> TryFrom<&str> for Mesh<T, I>
+where T: Send + FromStr + Default + ObjFloat, I: Send + FromStr + Copy + TryInto<i64> + TryFrom<i64>{
+    type Error = Vec<ParseError>;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let (mesh, errors) = parse_collecting_errors(input);
+        if errors.is_empty() { Ok(mesh) } else { Err(errors) }
+    }
+}
+
+#[inline]
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3]{ [a[0]-b[0], a[1]-b[1], a[2]-b[2]] }
+
+#[inline]
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3]{ [a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]] }
+
+#[inline]
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32{ a[0]*b[0] + a[1]*b[1] + a[2]*b[2] }
+
+#[inline]
+fn normalize(a: [f32; 3]) -> Option<[f32; 3]>{
+    let len = (a[0]*a[0] + a[1]*a[1] + a[2]*a[2]).sqrt();
+    if len > 0.0 && len.is_finite() { Some([a[0]/len, a[1]/len, a[2]/len]) } else { None }
+}
+
+impl<I> Face<I>
+where I: Copy + TryInto<i64>{
+    // Whether all the vertices are within epsilon of one plane, a non-planar quad looks different depending on how it's split
+    // The plane goes through the centroid with the Newell normal, triangles are always planar, out of range indices aren't
+    pub fn is_planar(&self, positions: &[[f32; 3]], epsilon: f32) -> bool{
+        let Some(points) = self.vertices().map(|v| resolve_index(v.coord_rindex, positions.len()).map(|i| positions[i])).collect::<Option<Vec<_>>>() else { return false; };
+        if points.len() <= 3 { return true; }
+        let mut newell = [0.0; 3];
+        for (a, b) in points.iter().zip(points.iter().cycle().skip(1)){
+            newell = [newell[0] + (a[1]-b[1])*(a[2]+b[2]), newell[1] + (a[2]-b[2])*(a[0]+b[0]), newell[2] + (a[0]-b[0])*(a[1]+b[1])];
+        }
+        let Some(normal) = normalize(newell) else { return true; }; // No area, so all on a line (or a point)
+        let centroid = points.iter().fold([0.0; 3], |c, p| [c[0]+p[0], c[1]+p[1], c[2]+p[2]]).map(|c| c / points.len() as f32);
+        points.iter().all(|&p| dot(sub(p, centroid), normal).abs() <= epsilon)
+    }
+}
+
+// Column-major like OpenGL and glam (m[column][row]), so the translation is m[3]
+impl<I> Mesh<f32, I>{
+    // Transforms positions by the whole (affine) matrix, normals by the inverse transpose of its 3x3 part and tangents by the 3x3 part
+    // Normals and tangents are renormalized, tangents flip handedness if the matrix mirrors
+    pub fn apply_matrix(&mut self, m: [[f32; 4]; 4]){
+        let (c0, c1, c2) = ([m[0][0], m[0][1], m[0][2]], [m[1][0], m[1][1], m[1][2]], [m[2][0], m[2][1], m[2][2]]);
+        let linear = |v: [f32; 3]| [0, 1, 2].map(|r| c0[r]*v[0] + c1[r]*v[1] + c2[r]*v[2]);
+        // The columns of the cofactor matrix, which is the inverse transpose times the determinant
+        let (n0, n1, n2) = (cross(c1, c2), cross(c2, c0), cross(c0, c1));
+        let sign = dot(c0, n0).signum();
+        for p in &mut self.positions{
+            let [x, y, z] = linear(*p);
+            *p = [x + m[3][0], y + m[3][1], z + m[3][2]];
+        }
+        for n in &mut self.normals{
+            let v = [0, 1, 2].map(|r| sign * (n0[r]*n[0] + n1[r]*n[1] + n2[r]*n[2]));
+            *n = normalize(v).unwrap_or(v);
+        }
+        for t in &mut self.tangents{
+            let v = linear([t[0], t[1], t[2]]);
+            let [x, y, z] = normalize(v).unwrap_or(v);
+            *t = [x, y, z, t[3] * sign];
+        }
+    }
+}
+
+impl Mesh<f32, i32>{
+    // Welds positions that are exactly the same (compared like dedup_vertices does) into the first of them, and points the coord
+    // indices of the faces at what's left, tex coords and normals are left alone, unlike dedup_vertices which welds whole vertices
+    // Coord indices end up absolute (like generate_normals leaves them), ones that don't resolve are kept as they were
+    pub fn dedup_positions_in_place(&mut self){
+        let bits = |f: f32| if f == 0.0 { 0 } else { f.to_bits() };
+        let mut seen: HashMap<[u32; 3], usize> = HashMap::new();
+        let mut positions = Vec::new();
+        let remap: Vec<usize> = self.positions.iter().map(|&p| *seen.entry(p.map(bits)).or_insert_with(||{
+            positions.push(p);
+            positions.len() - 1
+        })).collect();
+        for v in self.faces.iter_mut().flat_map(|f| f.vertices_mut()){
+            if let Some(i) = resolve_index(v.coord_rindex, remap.len()).and_then(|i| i32::try_from(remap[i] + 1).ok()){
+                v.coord_rindex = i;
+            }
+        }
+        self.positions = positions;
+    }
+}
+
+// Replaces the normals of the mesh with smooth ones, computed by averaging the normals of all the faces sharing a position
+// Every face vertex gets its normal index set to its coord index, faces with no area (or out of range indices) don't contribute
+// Faces with out of range indices get their normal indices cleared, the old ones would point into the replaced normals
+pub fn generate_normals(mesh: &mut Mesh<f32, i32>){
+    let npositions = mesh.positions.len();
+    let mut normals = vec![[0.0f32; 3]; npositions];
+    for face in &mut mesh.faces{
+        let mut face_normal = [0.0f32; 3];
+        let mut valid = true;
+        for [v1, v2, v3] in face.triangles(){
+            match (resolve_index(v1.coord_rindex, npositions), resolve_index(v2.coord_rindex, npositions), resolve_index(v3.coord_rindex, npositions)){
+                (Some(i1), Some(i2), Some(i3)) => {
+                    let p1 = mesh.positions[i1];
+                    let n = cross(sub(mesh.positions[i2], p1), sub(mesh.positions[i3], p1));
+                    face_normal = [face_normal[0]+n[0], face_normal[1]+n[1], face_normal[2]+n[2]];
+                },
+                _ => valid = false
+            }
+        }
+        if !valid {
+            for v in face.vertices_mut() { v.normal_rindex = None; }
+            continue;
+        }
+        for v in face.vertices_mut(){
+            let i = resolve_index(v.coord_rindex, npositions).unwrap(); // Checked above
+            if let Some(n) = normalize(face_normal){
+                normals[i] = [normals[i][0]+n[0], normals[i][1]+n[1], normals[i][2]+n[2]];
+            }
+            v.normal_rindex = i32::try_from(i + 1).ok(); // A relative index can reach further than an absolute one
+        }
+    }
+    mesh.normals = normals.into_iter().map(|n| normalize(n).unwrap_or(n)).collect();
+}
+
+// Replaces the tangents of the mesh with per-vertex ones (Lengyel's method), there is one for every normal since they get
+// orthogonalized against it, w is -1 if the bitangent is mirrored (cross(normal, tangent) * w gives it)
+// Triangles missing tex coords or normals (or with out of range indices) are skipped, normals no triangle uses get a zero tangent
+pub fn generate_tangents(mesh: &mut Mesh<f32, i32>){
+    let (npositions, ntexcoords, nnormals) = (mesh.positions.len(), mesh.texcoords.len(), mesh.normals.len());
+    let mut tan1 = vec![[0.0f32; 3]; nnormals];
+    let mut tan2 = vec![[0.0f32; 3]; nnormals];
+    let resolve = |v: &VertexIndeces<i32>|{
+        let v = v.resolve(npositions, ntexcoords, nnormals)?;
+        Some((v.coord_rindex, v.texcoord_rindex?, v.normal_rindex?))
+    };
+    for [v1, v2, v3] in mesh.faces.iter().flat_map(|f| f.triangles()){
+        let (Some(v1), Some(v2), Some(v3)) = (resolve(&v1), resolve(&v2), resolve(&v3)) else { continue; };
+        let (p1, uv1) = (mesh.positions[v1.0], mesh.texcoords[v1.1]);
+        let (e1, e2) = (sub(mesh.positions[v2.0], p1), sub(mesh.positions[v3.0], p1));
+        let (s1, t1) = (mesh.texcoords[v2.1][0] - uv1[0], mesh.texcoords[v2.1][1] - uv1[1]);
+        let (s2, t2) = (mesh.texcoords[v3.1][0] - uv1[0], mesh.texcoords[v3.1][1] - uv1[1]);
+        let r = 1.0 / (s1*t2 - s2*t1);
+        if !r.is_finite() { continue; } // The tex coords don't span an area
+        let sdir = [(e1[0]*t2 - e2[0]*t1)*r, (e1[1]*t2 - e2[1]*t1)*r, (e1[2]*t2 - e2[2]*t1)*r];
+        let tdir = [(e2[0]*s1 - e1[0]*s2)*r, (e2[1]*s1 - e1[1]*s2)*r, (e2[2]*s1 - e1[2]*s2)*r];
+        for (_, _, n) in [v1, v2, v3]{
+            for c in 0..3 { tan1[n][c] += sdir[c]; tan2[n][c] += tdir[c]; }
+        }
+    }
+    mesh.tangents = mesh.normals.iter().zip(tan1.into_iter().zip(tan2)).map(|(&n, (t, b))|{
+        let d = dot(n, t);
+        let Some(t) = normalize([t[0] - n[0]*d, t[1] - n[1]*d, t[2] - n[2]*d]) else { return [0.0; 4]; }; // Gram-Schmidt
+        let w = if dot(cross(n, t), b) < 0.0 { -1.0 } else { 1.0 };
+        [t[0], t[1], t[2], w]
+    }).collect();
+}
+
+// How many faces have tex coords and normals on all of their vertices, see attribute_consistency
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct AttributeReport{
+    pub faces: usize,
+    pub with_texcoords: usize,
+    pub with_normals: usize,
+    pub mixed_faces: usize // Faces that have an attribute on only some of their vertices
+}
+
+impl AttributeReport{
+    // True if every face has the same attributes, so they can all go in one vertex buffer
+    pub fn is_consistent(&self) -> bool{
+        self.mixed_faces == 0 && (self.with_texcoords == 0 || self.with_texcoords == self.faces) && (self.with_normals == 0 || self.with_normals == self.faces)
+    }
+}
+
+// Only looks at which indices are present, not whether they are in range
+pub fn attribute_consistency<T, I>(mesh: &Mesh<T, I>) -> AttributeReport{
+    let mut report = AttributeReport{ faces: mesh.faces.len(), ..Default::default() };
+    for face in &mesh.faces{
+        let texcoords = face.vertices().filter(|v| v.texcoord_rindex.is_some()).count();
+        let normals = face.vertices().filter(|v| v.normal_rindex.is_some()).count();
+        if texcoords == face.len() { report.with_texcoords += 1; }
+        if normals == face.len() { report.with_normals += 1; }
+        if (texcoords != 0 && texcoords != face.len()) || (normals != 0 && normals != face.len()) { report.mixed_faces += 1; }
+    }
+    report
+}
+
+// How many elements a file declares and which of its faces reference ones that don't exist, see count_consistency
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ConsistencyReport{
+    pub positions: usize,
+    pub texcoords: usize,
+    pub normals: usize,
+    pub bad_position_refs: usize,
+    pub bad_texcoord_refs: usize,
+    pub bad_normal_refs: usize,
+    pub bad_faces: Vec<usize> // Indices of the faces (counting only faces) with at least one bad reference
+}
+
+impl ConsistencyReport{
+    pub fn is_consistent(&self) -> bool{ self.bad_faces.is_empty() }
+}
+
+// Positive indices are checked against the element counts of the whole file, negative ones against the elements before the face
+pub fn count_consistency<T, I>(results: &[LineResult<'_, T, I>]) -> ConsistencyReport
+where I: Copy + TryInto<i64>{
+    let mut report = ConsistencyReport::default();
+    let totals = element_counts(results);
+    let mut seen = [0; 3];
+    let mut face_index = 0;
+    for line in results{
+        match line{
+            LineResult::VertDataLine(v) => seen[element_slot(v)] += 1,
+            LineResult::FaceLine(face) => {
+                let check = |i: I, slot: usize| resolve_index(i, if i.try_into().is_ok_and(|i: i64| i < 0) { seen[slot] } else { totals[slot] }).is_some();
+                let mut bad = false;
+                for v in face.vertices(){
+                    if !check(v.coord_rindex, 0) { report.bad_position_refs += 1; bad = true; }
+                    if v.texcoord_rindex.is_some_and(|i| !check(i, 1)) { report.bad_texcoord_refs += 1; bad = true; }
+                    if v.normal_rindex.is_some_and(|i| !check(i, 2)) { report.bad_normal_refs += 1; bad = true; }
+                }
+                if bad { report.bad_faces.push(face_index); }
+                face_index += 1;
+            },
+            _ => {}
+        }
+    }
+    [report.positions, report.texcoords, report.normals] = totals;
+    report
+}
+
+// Positions, tex coords and normals
+fn element_counts<T, I>(results: &[LineResult<'_, T, I>]) -> [usize; 3]{
+    let mut counts = [0; 3];
+    for line in results{
+        if let LineResult::VertDataLine(v) = line { counts[element_slot(v)] += 1; }
+    }
+    counts
+}
+
+fn element_slot<T>(v: &VertexData<T>) -> usize{
+    match v{
+        VertexData::Coord2{..} | VertexData::Coord3{..} | VertexData::Coord4{..} => 0,
+        VertexData::TextureCoord1{..} | VertexData::TextureCoord2{..} | VertexData::TextureCoord3{..} => 1,
+        VertexData::Normal{..} => 2,
+    }
+}
+
+// A position with a coordinate so large that f32 can't tell apart values closer than ulp, see precision_warnings
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PrecisionWarning{
+    pub position: usize, // 0-based
+    pub value: f32,
+    pub ulp: f32
+}
+
+impl std::fmt::Display for PrecisionWarning{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "position {}: {} is only precise to {}, parse as f64 instead", self.position, self.value, self.ulp)
+    }
+}
+
+// Positions with a coordinate whose ulp (the gap to the next f32) is more than max_ulp, one warning per position (for its largest coordinate)
+// Meant for geospatial files with huge coordinates, which f32 silently rounds (16777217 becomes 16777216)
+pub fn precision_warnings<I>(mesh: &Mesh<f32, I>, max_ulp: f32) -> Vec<PrecisionWarning>{
+    mesh.positions.iter().enumerate().filter_map(|(position, p)|{
+        let value = p.iter().copied().filter(|c| c.is_finite()).max_by(|a, b| a.abs().total_cmp(&b.abs()))?;
+        let ulp = f32::from_bits(value.abs().to_bits() + 1) - value.abs();
+        if ulp > max_ulp { Some(PrecisionWarning{ position, value, ulp }) } else { None }
+    }).collect()
+}
+
+// Whether two meshes have the same positions and faces, in any order and with floats within epsilon, for checking round trips
+// Faces are compared by what their vertices resolve to (position, tex coord and normal), so they match even if the elements got
+// renumbered, a face may start at any of its vertices but has to keep its winding, it's quadratic so only meant for small meshes
+pub fn mesh_eq_unordered<I, J>(a: &Mesh<f32, I>, b: &Mesh<f32, J>, epsilon: f32) -> bool
+where I: Copy + TryInto<i64>, J: Copy + TryInto<i64>{
+    let close = |x: &[f32], y: &[f32]| x.iter().zip(y).all(|(x, y)| (x - y).abs() <= epsilon);
+    let close_opt = |x: Option<[f32; 3]>, y: Option<[f32; 3]>| match (x, y){
+        (Some(x), Some(y)) => close(&x, &y),
+        (x, y) => x.is_none() && y.is_none()
+    };
+    let same_vertex = |x: &ResolvedVertex<f32>, y: &ResolvedVertex<f32>| close(&x.position, &y.position) && close_opt(x.texcoord, y.texcoord) && close_opt(x.normal, y.normal);
+    let same_face = |x: &Vec<ResolvedVertex<f32>>, y: &Vec<ResolvedVertex<f32>>| x.len() == y.len() && (0..y.len()).any(|r| x.iter().zip(y.iter().cycle().skip(r)).all(|(x, y)| same_vertex(x, y)));
+    let resolve = |faces: Vec<Option<Vec<ResolvedVertex<f32>>>>| faces.into_iter().collect::<Option<Vec<_>>>();
+    let (Some(faces_a), Some(faces_b)) = (
+        resolve(a.faces.iter().map(|f| f.vertices().map(|v| a.resolve_vertex(v)).collect()).collect()),
+        resolve(b.faces.iter().map(|f| f.vertices().map(|v| b.resolve_vertex(v)).collect()).collect())
+    ) else { return false; };
+    matches_unordered(&a.positions, &b.positions, |x, y| close(x, y)) && matches_unordered(&faces_a, &faces_b, same_face)
+}
+
+// Pairs up every element of a with a different one of b that's the same according to eq
+fn matches_unordered<A>(a: &[A], b: &[A], eq: impl Fn(&A, &A) -> bool) -> bool{
+    let mut used = vec![false; b.len()];
+    a.len() == b.len() && a.iter().all(|x| match (0..b.len()).find(|&i| !used[i] && eq(x, &b[i])){
+        Some(i) => { used[i] = true; true },
+        None => false
+    })
+}
+
+// Which parts of a vertex have to match for dedup_vertices to weld two of them together
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum DedupKey{
+    PositionOnly,
+    PositionNormal,
+    #[default]
+    PositionTexNormal
+}
+
+// Turns the triangles of the mesh (see Mesh::triangles) into an indexed vertex buffer, reusing a vertex if its key matches
+// one already in the buffer, welded vertices keep the attributes of the first one
+// Vertices are numbered in the order they're first seen, the hash map is only used for lookups, so the output is reproducible
+// Floats are compared bitwise (except that 0.0 == -0.0), so nearly equal ones don't get welded
+// None if there are more distinct vertices than a u32 index buffer can address
+pub fn dedup_vertices<I>(mesh: &Mesh<f32, I>, key: DedupKey) -> Option<(Vec<ResolvedVertex<f32>>, Vec<u32>)>
+where I: Copy + TryInto<i64>{
+    let bits = |f: f32| if f == 0.0 { 0 } else { f.to_bits() };
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut seen: HashMap<[Option<u32>; 9], u32> = HashMap::new(); // Position, tex coord and normal, None if not part of the key
+    for v in mesh.triangles().flatten(){
+        let [x, y, z] = v.position.map(|c| Some(bits(c)));
+        let [s, t, r] = match (key, v.texcoord){
+            (DedupKey::PositionTexNormal, Some(uvw)) => uvw.map(|c| Some(bits(c))),
+            _ => [None; 3]
+        };
+        let [nx, ny, nz] = match (key, v.normal){
+            (DedupKey::PositionNormal | DedupKey::PositionTexNormal, Some(n)) => n.map(|c| Some(bits(c))),
+            _ => [None; 3]
+        };
+        let k = [x, y, z, s, t, r, nx, ny, nz];
+        let i = match seen.entry(k){
+            Entry::Occupied(e) => *e.get(),
+            Entry::Vacant(e) => {
+                let i = u32::try_from(vertices.len()).ok()?;
+                vertices.push(v);
+                *e.insert(i)
+            }
+        };
+        indices.push(i);
+    }
+    Some((vertices, indices))
+}
+
+// The simple alternative to dedup_vertices, every corner of every triangle gets its own vertex and the indices are just 0, 1, 2, ...
+// Cheaper to build, but the vertex buffer is as big as it gets (see the dedup bench for how the two compare)
+// None if there are more vertices than a u32 index buffer can address, like dedup_vertices
+pub fn expand_vertices<T, I>(mesh: &Mesh<T, I>) -> Option<(Vec<ResolvedVertex<T>>, Vec<u32>)>
+where T: Copy, I: Copy + TryInto<i64>{
+    let vertices: Vec<ResolvedVertex<T>> = mesh.triangles().flatten().collect();
+    let indices = (0..u32::try_from(vertices.len()).ok()?).collect();
+    Some((vertices, indices))
+}
+
+impl<I> Mesh<f32, I>
+where I: Copy + TryInto<i64>{
+    // The welded vertices of dedup_vertices (with DedupKey::PositionTexNormal) as a single interleaved buffer of
+    // 8 floats per vertex (position xyz, normal xyz, tex coord uv) along with the index buffer, ready to upload to a GPU
+    // Vertices without a normal or tex coord get zeros for it, the w of 3d tex coords is left out
+    // None if dedup_vertices is, there are too many vertices for u32 indices
+    pub fn to_interleaved(&self) -> Option<(Vec<f32>, Vec<u32>)>{
+        let (vertices, indices) = dedup_vertices(self, DedupKey::PositionTexNormal)?;
+        let mut buffer = Vec::with_capacity(vertices.len() * 8);
+        for vertex in vertices{
+            let [x, y, z] = vertex.position;
+            let [nx, ny, nz] = vertex.normal.unwrap_or_default();
+            let [u, v, _] = vertex.texcoord.unwrap_or_default();
+            buffer.extend([x, y, z, nx, ny, nz, u, v]);
+        }
+        Some((buffer, indices))
+    }
+}
+
+// Turns the triangles of the mesh into a single triangle strip of (0-based) position indices, keeping their winding
+// Strips are grown greedily across shared edges, separate ones are joined with degenerate triangles (which GPUs skip),
+// triangles with out of range indices, indices that don't fit in a u32, or with a repeated position are left out
+pub fn to_triangle_strip<T, I>(mesh: &Mesh<T, I>) -> Vec<u32>
+where I: Copy + TryInto<i64>{
+    let npositions = mesh.positions.len();
+    let triangles: Vec<[u32; 3]> = mesh.faces.iter().flat_map(|f| f.triangles()).filter_map(|tri|{
+        let [a, b, c] = tri.map(|v| resolve_index(v.coord_rindex, npositions).and_then(|i| u32::try_from(i).ok()));
+        let tri = [a?, b?, c?];
+        (tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0]).then_some(tri)
+    }).collect();
+    // Every directed edge a->b (in the winding order of its triangle) with that triangle, sorted so the triangles of an edge
+    // are next to each other in order and found with a binary search, that's much cheaper to build than a HashMap of Vecs
+    let mut edges: Vec<((u32, u32), usize)> = triangles.iter().enumerate().flat_map(|(i, &[a, b, c])| [((a, b), i), ((b, c), i), ((c, a), i)]).collect();
+    edges.sort_unstable();
+    let triangles_of = |edge: (u32, u32)| edges[edges.partition_point(|&(e, _)| e < edge)..].iter().take_while(move |&&(e, _)| e == edge).map(|&(_, t)| t);
+    let mut used = vec![false; triangles.len()];
+    // Which attempt at growing a strip last took each triangle, so checking if the current one did doesn't have to search it
+    let mut taken_by = vec![0usize; triangles.len()];
+    let mut attempt = 0;
+    // Grows a strip starting with the given corners, for odd triangles in a strip the first two corners are swapped
+    let mut grow = |start: [u32; 3], used: &[bool]|{
+        attempt += 1;
+        let mut strip = start.to_vec();
+        let mut taken = Vec::new();
+        loop{
+            let (p, q) = (strip[strip.len() - 2], strip[strip.len() - 1]);
+            let edge = if strip.len() % 2 == 1 { (q, p) } else { (p, q) }; // The edge the next triangle has to wind along
+            let next = triangles_of(edge).find(|&t| !used[t] && taken_by[t] != attempt);
+            let Some(t) = next else { break; };
+            let [a, b, c] = triangles[t];
+            strip.push(if (a, b) == edge { c } else if (b, c) == edge { a } else { b });
+            taken_by[t] = attempt;
+            taken.push(t);
+        }
+        (strip, taken)
+    };
+    let mut out: Vec<u32> = Vec::new();
+    for i in 0..triangles.len(){
+        if used[i] { continue; }
+        used[i] = true;
+        let [a, b, c] = triangles[i];
+        // max_by_key keeps the last of equally long ones, so reversed to prefer starting with the triangle as it is
+        let (strip, taken) = [[a, b, c], [b, c, a], [c, a, b]].into_iter().rev().map(|start| grow(start, &used)).max_by_key(|(strip, _)| strip.len()).unwrap();
+        for t in taken { used[t] = true; }
+        if let Some(&last) = out.last(){
+            out.push(last);
+            if out.len().is_multiple_of(2) { out.push(last); } // The next strip has to start on an even triangle to keep its winding
+            out.push(strip[0]);
+        }
+        out.extend(strip);
+    }
+    out
+}
+
+// Sum of the areas of all the triangles in the mesh (see Mesh::triangles), degenerate triangles just add nothing
+pub fn surface_area(mesh: &Mesh<f32, i32>) -> f32{
+    mesh.triangles().map(|[v1, v2, v3]|{
+        let n = cross(sub(v2.position, v1.position), sub(v3.position, v1.position));
+        let area = 0.5 * (n[0]*n[0] + n[1]*n[1] + n[2]*n[2]).sqrt();
+        if area.is_finite() { area } else { 0.0 }
+    }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_elements;
+
+    const CUBE: &str = "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1\n\
+                        f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6\n";
+
+    #[test]
+    fn test_collect_mesh(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 1 2\nvt 0.5\nvn 0 1 0\nf 1/1/1 1/1/1 1/1/1\n").collect::<Vec<_>>());
+        assert_eq!(mesh.positions, vec![[1.0, 2.0, 0.0]]);
+        assert_eq!(mesh.texcoords, vec![[0.5, 0.0, 0.0]]);
+        assert_eq!(mesh.normals, vec![[0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.faces.len(), 1);
+    }
+
+    #[test]
+    fn test_texcoord_defaults(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("vt 0.5\nvt 0.5 0.25\nvt 0.5 0.25 1\n").collect::<Vec<_>>());
+        assert_eq!(mesh.texcoords, vec![[0.5, 0.0, 0.0], [0.5, 0.25, 0.0], [0.5, 0.25, 1.0]]);
+    }
+
+    #[test]
+    fn test_extend(){
+        let mut mesh: Mesh<f32, i32> = Mesh::default();
+        mesh.extend(parse_file("v 0 0 0\nv 1 0 0\nv 0 1 0\n").collect::<Vec<_>>());
+        mesh.extend(parse_file("vn 0 0 1\nf 1//1 2//1 3//1\ng rest\n").collect::<Vec<_>>());
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]]);
+        assert_eq!(mesh.faces.len(), 1);
+        assert!(mesh.resolve_vertex(mesh.faces[0].vertex(2).unwrap()).is_some());
+    }
+
+    #[test]
+    fn test_relative_face_before_more_vertices(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//-1 -2//-1 -1//-1\nv 5 5 5\nvn 1 0 0\nf -4 -1 -2\nf -9 1 2\n";
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        let triangles: Vec<[ResolvedVertex<f32>; 3]> = mesh.triangles().collect();
+        assert_eq!(triangles.len(), 2); // The last face points before the first position
+        assert_eq!(triangles[0].map(|v| v.position), [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(triangles[0].map(|v| v.normal), [Some([0.0, 0.0, 1.0]); 3]);
+        assert_eq!(triangles[1].map(|v| v.position), [[0.0, 0.0, 0.0], [5.0, 5.0, 5.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(mesh.faces[2].vertex(0).unwrap().coord_rindex, 0);
+        let zero_based: Mesh<f32, usize> = collect_mesh_zero_based(parse_file::<f32, i32>(input).collect::<Vec<_>>());
+        assert_eq!(zero_based.faces[0].vertices().map(|v| v.coord_rindex).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!((surface_area(&mesh) - (0.5 + surface_area(&collect_mesh(parse_file("v 0 0 0\nv 5 5 5\nv 0 1 0\nf 1 2 3").collect::<Vec<_>>())))).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_collect_mesh_zero_based(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nvn 0 0 1\nf -3//1 -2//-1 -1//1\nf 1 2 4\nv 1 1 0\nf 4 -1 1\n";
+        let mesh: Mesh<f32, usize> = collect_mesh_zero_based(parse_file::<f32, i32>(input).collect::<Vec<_>>());
+        let faces: Vec<Vec<_>> = mesh.faces.iter().map(|f| f.vertices().map(|v| (v.coord_rindex, v.normal_rindex)).collect()).collect();
+        assert_eq!(faces, vec![
+            vec![(0, None), (1, None), (2, None)],
+            vec![(0, Some(0)), (1, Some(0)), (2, Some(0))],
+            vec![(3, None), (3, None), (0, None)], // f 1 2 4 is left out, there is no 4th position yet
+        ]);
+        let raw: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nf 1 2 3\n").collect::<Vec<_>>());
+        assert_eq!(raw.faces[0].vertex(0).unwrap().coord_rindex, 1); // collect_mesh keeps them as they are
+    }
+
+    #[test]
+    fn test_collect_mesh_with_capacity(){
+        let counts = count_elements(CUBE);
+        assert_eq!(counts, ElementCounts{ positions: 8, texcoords: 0, normals: 0, faces: 6 });
+        let hint = ElementCounts{ positions: 100, texcoords: 10, normals: 20, faces: 50 };
+        let mesh: Mesh<f32, i32> = collect_mesh_with_capacity(parse_file(CUBE).collect::<Vec<_>>(), hint);
+        assert_eq!(mesh.positions.len(), 8);
+        assert!(mesh.positions.capacity() >= 100);
+        assert!(mesh.texcoords.capacity() >= 10);
+        assert!(mesh.normals.capacity() >= 20);
+        assert!(mesh.faces.capacity() >= 50);
+    }
+
+    #[test]
+    fn test_split_objects(){
+        let input = "mtllib a.mtl\no first\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\no second\nv 0 0 1\nv 1 0 1\nv 0 1 1\nvn 0 0 1\nf 4//1 5//1 -1//-1\n";
+        let objects: Vec<(_, Mesh<f32, i32>)> = split_objects(parse_file(input).collect::<Vec<_>>());
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].0.as_deref(), Some("first"));
+        assert_eq!(objects[1].0.as_deref(), Some("second"));
+        assert_eq!(objects[1].1.positions[0], [0.0, 0.0, 1.0]);
+        let face: Vec<_> = objects[1].1.faces[0].vertices().map(|v| (v.coord_rindex, v.normal_rindex)).collect();
+        assert_eq!(face, vec![(1, Some(1)), (2, Some(1)), (3, Some(1))]); // -1 made absolute within the object
+        assert_eq!(objects[1].1.triangles().count(), 1);
+    }
+
+    #[test]
+    fn test_generate_normals_cube(){
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(CUBE).collect::<Vec<_>>());
+        generate_normals(&mut mesh);
+        assert_eq!(mesh.normals.len(), 8);
+        for (n, p) in mesh.normals.iter().zip(&mesh.positions){
+            let expected = normalize(*p).unwrap(); // The corners of a centered cube point straight out
+            for c in 0..3 { assert!((n[c] - expected[c]).abs() < 1e-6); }
+        }
+        for v in mesh.faces.iter().flat_map(|f| f.vertices()){
+            assert_eq!(v.normal_rindex, Some(v.coord_rindex));
+        }
+    }
+
+    #[test]
+    fn test_generate_normals_degenerate(){
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 1 2\n").collect::<Vec<_>>());
+        generate_normals(&mut mesh);
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 3]);
+
+        // The face that's skipped doesn't keep pointing at the 5th normal, there are only 3 now
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(&("v 0 0 0\nv 1 0 0\nv 0 1 0\n".to_string() + &"vn 0 0 1\n".repeat(5) + "f 1//5 2//5 3//5\nf 1//5 2//5 9//5\n")).collect::<Vec<_>>());
+        generate_normals(&mut mesh);
+        assert_eq!(mesh.normals.len(), 3);
+        assert!(mesh.faces[0].vertices().all(|v| v.normal_rindex == Some(v.coord_rindex)));
+        assert!(mesh.faces[1].vertices().all(|v| v.normal_rindex.is_none()));
+    }
+
+    #[test]
+    fn test_generate_tangents(){
+        let quad = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nvn 0 0 1\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n";
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(&(quad.to_string() + "f 1/1/1 2/2/1 3/3/1 4/4/1\n")).collect::<Vec<_>>());
+        generate_tangents(&mut mesh);
+        assert_eq!(mesh.tangents, vec![[1.0, 0.0, 0.0, 1.0]]); // u goes along +x, v along +y
+        let mut mirrored: Mesh<f32, i32> = collect_mesh(parse_file(&(quad.to_string() + "f 1/2/1 2/1/1 3/4/1 4/3/1\n")).collect::<Vec<_>>());
+        generate_tangents(&mut mirrored);
+        assert_eq!(mirrored.tangents, vec![[-1.0, 0.0, 0.0, -1.0]]);
+        let mut untextured: Mesh<f32, i32> = collect_mesh(parse_file(&(quad.to_string() + "f 1//1 2//1 3//1\n")).collect::<Vec<_>>());
+        generate_tangents(&mut untextured);
+        assert_eq!(untextured.tangents, vec![[0.0; 4]]);
+    }
+
+    #[test]
+    fn test_is_planar(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 1 0.5\nf 1 2 3 4\nf 1 2 3 5\nf 1 2 9\n").collect::<Vec<_>>());
+        assert!(mesh.faces[0].is_planar(&mesh.positions, 1e-5));
+        assert!(!mesh.faces[1].is_planar(&mesh.positions, 1e-5));
+        assert!(mesh.faces[1].is_planar(&mesh.positions, 0.2)); // Every corner is about 0.12 off the plane
+        assert!(!mesh.faces[2].is_planar(&mesh.positions, 1e-5));
+    }
+
+    #[test]
+    fn test_apply_matrix(){
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 1 0 0\nvn 1 0 0\n").collect::<Vec<_>>());
+        // 90 degrees around z, then a translation by (0, 0, 5)
+        mesh.apply_matrix([[0.0, 1.0, 0.0, 0.0], [-1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 5.0, 1.0]]);
+        assert_eq!(mesh.positions, vec![[0.0, 1.0, 5.0]]);
+        assert_eq!(mesh.normals, vec![[0.0, 1.0, 0.0]]);
+        // A non-uniform scale squashes positions but has to keep normals perpendicular to the surface
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 1 1 0\nvn 1 1 0\n").collect::<Vec<_>>());
+        mesh.apply_matrix([[2.0, 0.0, 0.0, 0.0], [0.0, 1.0, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]);
+        assert_eq!(mesh.positions, vec![[2.0, 1.0, 0.0]]);
+        let expected = normalize([0.5, 1.0, 0.0]).unwrap();
+        assert!(mesh.normals[0].iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_precision_warnings(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 1000.5 0 0\nv 0 16777217.0 1\nv -3e9 0 0\n").collect::<Vec<_>>());
+        let warnings = precision_warnings(&mesh, 1.0);
+        assert_eq!(warnings, vec![PrecisionWarning{ position: 1, value: 16777216.0, ulp: 2.0 }, PrecisionWarning{ position: 2, value: -3e9, ulp: 256.0 }]);
+        assert_eq!(warnings[0].to_string(), "position 1: 16777216 is only precise to 2, parse as f64 instead");
+        assert!(precision_warnings(&mesh, 1000.0).is_empty());
+    }
+
+    #[test]
+    fn test_parse_file_take_faces(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nv 1 1 0\nf 2 4 3\nf 1 2 4\n";
+        let mesh: Mesh<f32, i32> = parse_file_take_faces(input, 1);
+        assert_eq!(mesh.faces.len(), 1);
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(parse_file_take_faces::<f32, i32>(input, 2).positions.len(), 4);
+        assert_eq!(parse_file_take_faces::<f32, i32>(input, 10), collect_mesh(parse_file(input).collect::<Vec<_>>()));
+        assert!(parse_file_take_faces::<f32, i32>(input, 0).is_empty());
+    }
+
+    #[test]
+    fn test_flip_normals(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 2 2 0\nvn 0 0 -1\nf 1//1 3//1 2//1\nf 1 2 4 3\nf 1 2 5 4 3\n";
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        mesh.flip_normals();
+        assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]]);
+        mesh.reverse_winding();
+        let coords = |f: &Face<i32>| f.vertices().map(|v| v.coord_rindex).collect::<Vec<_>>();
+        assert_eq!(mesh.faces.iter().map(coords).collect::<Vec<_>>(), vec![vec![1, 2, 3], vec![1, 3, 4, 2], vec![1, 3, 4, 5, 2]]);
+        assert!(matches!(mesh.faces[0], Face::Face3{ v2: VertexIndeces{ normal_rindex: Some(1), .. }, .. }));
+        // The triangle was inside-out with a normal to match, turned around it still agrees with its (flipped) normal
+        let mut generated = mesh.clone();
+        generated.faces.truncate(1);
+        generate_normals(&mut generated);
+        assert_eq!(generated.normals[0], mesh.normals[0]);
+        // A FaceN built by hand can be empty, turning it around leaves it empty
+        let mut empty: Face<i32> = Face::FaceN{ verts: Default::default() };
+        empty.reverse_winding();
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_collect_mesh_mixed_texcoords(){
+        let input = "vt 0.5 0.25\nvt 0.75\nvt 0.1 0.2\n";
+        let results = || parse_file::<f32, i32>(input).collect::<Vec<_>>();
+        let mesh = collect_mesh_mixed_texcoords(results(), false).unwrap();
+        assert_eq!(mesh.texcoords, vec![[0.5, 0.25, 0.0], [0.75, 0.0, 0.0], [0.1, 0.2, 0.0]]);
+        assert_eq!(mesh, collect_mesh(results()));
+        let err = collect_mesh_mixed_texcoords(results(), true).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected 2 tex coord components like the first vt, found 1");
+        assert!(collect_mesh_mixed_texcoords(parse_file::<f32, i32>("vt 1\nv 0 0 0\nvt 0\n").collect::<Vec<_>>(), true).is_ok());
+    }
+
+    #[test]
+    fn test_mesh_eq_unordered(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\nf 2//1 4//1 3//1\n";
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        // Writes the positions backwards and the faces starting at their second vertex, so nothing is where it was
+        let mut writer = crate::ObjWriter::new(Vec::new());
+        for p in mesh.positions.iter().rev() { writer.write_vertex(p[0], p[1], p[2]).unwrap(); }
+        writer.write_normal(0.0, 0.0, 1.0).unwrap();
+        for face in mesh.faces.iter().rev(){
+            let [v1, v2, v3] = face.vertices().map(|v| VertexIndeces{ coord_rindex: 5 - v.coord_rindex, ..*v }).collect::<Vec<_>>().try_into().unwrap();
+            writer.write_face(&Face::Face3{ v1: v2, v2: v3, v3: v1 }).unwrap();
+        }
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+        let reparsed: Mesh<f32, i32> = collect_mesh(parse_file(&written).collect::<Vec<_>>());
+        assert_ne!(reparsed.faces, mesh.faces);
+        assert!(mesh_eq_unordered(&mesh, &reparsed, 1e-6));
+
+        let mut nudged = reparsed.clone();
+        nudged.positions[0][0] += 1e-4;
+        assert!(mesh_eq_unordered(&mesh, &nudged, 1e-3));
+        assert!(!mesh_eq_unordered(&mesh, &nudged, 1e-6));
+        let mut flipped = reparsed.clone();
+        let Face::Face3{ v1, v2, .. } = &mut flipped.faces[0] else { panic!("Wrong face type!"); };
+        std::mem::swap(v1, v2);
+        assert!(!mesh_eq_unordered(&mesh, &flipped, 1e-6));
+        let mut fewer = reparsed;
+        fewer.faces.pop();
+        assert!(!mesh_eq_unordered(&mesh, &fewer, 1e-6));
+    }
+
+    #[test]
+    fn test_dedup_positions_in_place(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nvt 1 0\nf 1/1 2/1 3/1\nf 4/2 5/2 -3/2\nf 1 9 2\n";
+        let mut mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        mesh.dedup_positions_in_place();
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]]);
+        let indices = |f: &Face<i32>| f.vertices().map(|v| (v.coord_rindex, v.texcoord_rindex)).collect::<Vec<_>>();
+        assert_eq!(indices(&mesh.faces[0]), vec![(1, Some(1)), (2, Some(1)), (3, Some(1))]);
+        // The second (1, 0, 0) is welded into the first, the relative index is made absolute and the tex coords don't change
+        assert_eq!(indices(&mesh.faces[1]), vec![(2, Some(2)), (4, Some(2)), (3, Some(2))]);
+        assert_eq!(indices(&mesh.faces[2]), vec![(1, None), (9, None), (2, None)]);
+    }
+
+    #[test]
+    fn test_dedup_vertices(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 0\nvn 0 0 1\nvn 0 0 -1\nf 1//1 2//1 3//1\nf 4//2 3//1 2//1\n";
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        let (vertices, indices) = dedup_vertices(&mesh, DedupKey::PositionOnly).unwrap();
+        assert_eq!(vertices.len(), 3); // 1 and 4 are at the same place
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 1]);
+        assert_eq!(vertices[0].normal, Some([0.0, 0.0, 1.0]));
+        let (vertices, indices) = dedup_vertices(&mesh, DedupKey::PositionTexNormal).unwrap();
+        assert_eq!(vertices.len(), 4); // But their normals differ
+        assert_eq!(indices, vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(dedup_vertices(&mesh, DedupKey::PositionNormal).unwrap().0.len(), 4);
+    }
+
+    #[test]
+    fn test_to_interleaved(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvt 1 1\nvn 0 0 1\nf 1/1/1 2/2/1 4/4/1 3/3/1\nf 1 3 2\n";
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+        let (buffer, indices) = mesh.to_interleaved().unwrap();
+        // The quad's 4 vertices are shared by its 2 triangles, the last face has the same positions but no tex coords or normals
+        assert_eq!(buffer.len(), 8 * 7);
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6]);
+        let vertex = |i: u32| &buffer[i as usize * 8..i as usize * 8 + 8];
+        assert_eq!(vertex(indices[2]), &[1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        assert_eq!(vertex(indices[8]), &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        for (i, [a, b, c]) in mesh.triangles().enumerate().map(|(i, t)| (i, t.map(|v| v.position))){
+            assert_eq!([&vertex(indices[i*3])[..3], &vertex(indices[i*3+1])[..3], &vertex(indices[i*3+2])[..3]], [&a[..], &b[..], &c[..]]);
+        }
+    }
+
+    #[test]
+    fn test_dedup_never_grows(){
+        let inputs = [
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 4/1/1 3/1/1\nf 1 2 3\n",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 2 3\nf -3 -2 -1\n",
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 2 3\nf 1 2 4 3 9\n",
+            "v 0 0 0\n",
+        ];
+        for input in inputs{
+            let mesh: Mesh<f32, i32> = collect_mesh(parse_file(input).collect::<Vec<_>>());
+            let (expanded, expanded_indices) = expand_vertices(&mesh).unwrap();
+            assert_eq!(expanded_indices, (0..expanded.len() as u32).collect::<Vec<_>>());
+            for key in [DedupKey::PositionOnly, DedupKey::PositionNormal, DedupKey::PositionTexNormal]{
+                let (deduped, indices) = dedup_vertices(&mesh, key).unwrap();
+                assert!(deduped.len() <= expanded.len());
+                assert_eq!(indices.len(), expanded_indices.len());
+                assert!(indices.iter().zip(&expanded).all(|(&i, v)| deduped[i as usize].position == v.position));
+            }
+        }
+    }
+
+    #[test]
+    fn test_dedup_vertices_deterministic(){
+        let mut input = String::from("vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n");
+        input += &CUBE.replace("f 1 4 3 2", "f 1/1 4/2 3/3 2/4");
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(&input).collect::<Vec<_>>());
+        let first = dedup_vertices(&mesh, DedupKey::PositionTexNormal).unwrap();
+        for _ in 0..10{
+            assert_eq!(dedup_vertices(&mesh, DedupKey::PositionTexNormal).unwrap(), first); // Every HashMap gets a new random seed
+        }
+        assert_eq!(first.1[..4], [0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_surface_area(){
+        let square: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4\nf 1 1 2\n").collect::<Vec<_>>());
+        assert_eq!(surface_area(&square), 1.0);
+        let cube: Mesh<f32, i32> = collect_mesh(parse_file(CUBE).collect::<Vec<_>>());
+        assert_eq!(surface_area(&cube), 24.0);
+    }
+
+    #[test]
+    fn test_triangles(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1 2 3\nf 1//1 2//1 3//1 4//1\nf 1 2 9\n").collect::<Vec<_>>());
+        assert_eq!(mesh.triangles().count(), 3); // The quad yields 2, the one with an out of range index none
+        let mut n = 0;
+        for [v1, v2, v3] in &mesh{
+            assert_eq!(v1.position, [0.0, 0.0, 0.0]);
+            assert_ne!(v2.position, v3.position);
+            n += 1;
+        }
+        let quad: Vec<_> = mesh.triangles().skip(1).collect();
+        assert_eq!(quad[1][2].position, [0.0, 1.0, 0.0]);
+        assert_eq!(quad[1][2].normal, Some([0.0, 0.0, 1.0]));
+        assert_eq!(n, 3);
+
+        // A big polygon is walked once, not restarted for every triangle
+        let n = 40_000;
+        let mut input: String = (0..n).map(|i| format!("v {} {} 0\n", (i as f32).cos(), (i as f32).sin())).collect();
+        input += &format!("f{}\n", (1..=n).map(|i| format!(" {}", i)).collect::<String>());
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(&input).collect::<Vec<_>>());
+        let t = std::time::Instant::now();
+        assert_eq!(mesh.triangles().count(), n - 2);
+        assert!(t.elapsed() < std::time::Duration::from_millis(500));
+        assert_eq!(mesh.triangles().last().unwrap().map(|v| v.position), [mesh.positions[0], mesh.positions[n - 2], mesh.positions[n - 1]]);
+    }
+
+    #[test]
+    fn test_faces_with_positions(){
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 -1\nf 1 2 4\n").collect::<Vec<_>>());
+        let faces: Vec<_> = faces_with_positions(&mesh).collect();
+        assert_eq!(faces.len(), 1); // The second one is out of range
+        assert!(std::ptr::eq(faces[0].0, &mesh.faces[0]));
+        assert_eq!(faces[0].1, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        // Positions after a relative face don't change what it refers to
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5\nf -1 -2 -3\n").collect::<Vec<_>>());
+        let positions: Vec<_> = faces_with_positions(&mesh).map(|(_, positions)| positions).collect();
+        assert_eq!(positions, vec![
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            vec![[5.0, 5.0, 5.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]],
+        ]);
+    }
+
+    #[test]
+    fn test_attribute_consistency(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n";
+        let uniform: Mesh<f32, i32> = collect_mesh(parse_file(&(input.to_string() + "f 1/1/1 2/1/1 3/1/1\nf 3/1/1 2/1/1 1/1/1\n")).collect::<Vec<_>>());
+        assert!(attribute_consistency(&uniform).is_consistent());
+        let mixed: Mesh<f32, i32> = collect_mesh(parse_file(&(input.to_string() + "f 1//1 2//1 3//1\nf 1 2 3\nf 1/1 2 3\n")).collect::<Vec<_>>());
+        let report = attribute_consistency(&mixed);
+        assert_eq!(report, AttributeReport{ faces: 3, with_texcoords: 0, with_normals: 1, mixed_faces: 1 });
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn test_count_consistency(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 -1\nf 1//1 2//2 3//1\nf 1//1 2//5 3//2\nf -1//-1 -2//-2 -3//-3\n";
+        let results: Vec<LineResult<f32, i32>> = parse_file(input).collect();
+        let report = count_consistency(&results);
+        assert_eq!(report, ConsistencyReport{ positions: 3, texcoords: 0, normals: 2, bad_position_refs: 0, bad_texcoord_refs: 0, bad_normal_refs: 2, bad_faces: vec![1, 2] });
+        assert!(!report.is_consistent());
+        let results: Vec<LineResult<f32, i32>> = parse_file("v 0 0 0\nf 1 1 1\n").collect();
+        assert!(count_consistency(&results).is_consistent());
+    }
+
+    // The triangles of a strip, in their original winding and rotated to start with the smallest index
+    fn strip_triangles(strip: &[u32]) -> Vec<[u32; 3]>{
+        let mut tris: Vec<[u32; 3]> = strip.windows(3).enumerate().filter(|(_, w)| w[0] != w[1] && w[1] != w[2] && w[2] != w[0]).map(|(k, w)|{
+            let mut tri = if k % 2 == 0 { [w[0], w[1], w[2]] } else { [w[1], w[0], w[2]] };
+            while tri[0] > tri[1] || tri[0] > tri[2] { tri.rotate_left(1); }
+            tri
+        }).collect();
+        tris.sort();
+        tris
+    }
+
+    #[test]
+    fn test_to_triangle_strip(){
+        // A 3x2 grid of quads
+        let mut input = String::new();
+        for y in 0..3 { for x in 0..4 { input += &format!("v {} {} 0\n", x, y); } }
+        for y in 0..2 { for x in 1..4 { let i = y * 4 + x; input += &format!("f {} {} {} {}\n", i, i + 1, i + 5, i + 4); } }
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file(&input).collect::<Vec<_>>());
+        let strip = to_triangle_strip(&mesh);
+        let mut expected: Vec<[u32; 3]> = mesh.faces.iter().flat_map(|f| f.triangles()).map(|tri|{
+            let mut tri = tri.map(|v| v.coord_rindex as u32 - 1);
+            while tri[0] > tri[1] || tri[0] > tri[2] { tri.rotate_left(1); }
+            tri
+        }).collect();
+        expected.sort();
+        assert_eq!(strip_triangles(&strip), expected);
+        assert!(strip.len() < expected.len() * 3); // Less than a plain triangle list
+        assert_eq!(strip.len(), 33); // 4 strips and their joins
+
+        let mesh: Mesh<f32, i32> = collect_mesh(parse_file("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 1 2\nf 1 2 9\n").collect::<Vec<_>>());
+        assert_eq!(to_triangle_strip(&mesh), vec![0, 1, 2]);
+
+        // Positions past u32::MAX can't be in the strip, zero sized ones so the test doesn't need the memory for them
+        // (truncated these would be positions 1 and 2, the same triangle as the other one)
+        let positions: Vec<[(); 3]> = std::iter::repeat_n([(); 3], u32::MAX as usize + 4).collect();
+        let high = u32::MAX as i64 + 3;
+        let faces = vec![Face::Face3{ v1: VertexIndeces{ coord_rindex: high, texcoord_rindex: None, normal_rindex: None },
+            v2: VertexIndeces{ coord_rindex: high + 1, texcoord_rindex: None, normal_rindex: None },
+            v3: VertexIndeces{ coord_rindex: 1, texcoord_rindex: None, normal_rindex: None } },
+            Face::Face3{ v1: VertexIndeces{ coord_rindex: 1, texcoord_rindex: None, normal_rindex: None },
+            v2: VertexIndeces{ coord_rindex: 2, texcoord_rindex: None, normal_rindex: None },
+            v3: VertexIndeces{ coord_rindex: 3, texcoord_rindex: None, normal_rindex: None } }];
+        let mesh: Mesh<(), i64> = Mesh{ positions, faces, ..Default::default() };
+        assert_eq!(to_triangle_strip(&mesh), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_parse_collecting_errors(){
+        let input = "v 0 0 0\nv 1 0 0\nv oops\nv 0 1 0\nf 1 2\nf 1 2 3\n";
+        let (mesh, errors) = parse_collecting_errors::<f32, i32>(input);
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.faces.len(), 1);
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![3, 5]);
+        assert!(Mesh::<f32, i32>::try_from(input).is_err());
+        assert_eq!(Mesh::<f32, i32>::try_from("v 0 0 0\nf 1 1 1").unwrap().faces.len(), 1);
+    }
+}
@@ -0,0 +1,126 @@
+// Parsing of .mtl (material library) files, only what's needed to find textures for now
+use std::borrow::Cow;
+
+use nom::{IResult, branch::alt, bytes::complete::{is_not, tag}, character::complete::{one_of, space0, space1}, combinator::{eof, map, opt, peek, recognize, value}, multi::many0, sequence::{preceded, terminated, tuple}};
+
+use crate::{ParseOptions, consume_num, end_line, parse_float, parse_name};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MtlLine<'a>{
+    NewMtl(Cow<'a, str>),
+    // map is the statement (map_Kd, map_Bump, ...), any options before the file are skipped
+    TextureMap{ map: Cow<'a, str>, file: Cow<'a, str> },
+    // From either d (opacity itself) or Tr (1 - opacity)
+    Opacity(f32),
+    NoData
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Material{
+    pub name: String,
+    // (statement, file) pairs, like ("map_Kd", "brick.png")
+    pub texture_maps: Vec<(String, String)>,
+    // 1.0 is fully opaque, if both d and Tr are given the last one wins
+    pub opacity: f32
+}
+
+impl Default for Material{
+    fn default() -> Self {
+        Self{ name: String::new(), texture_maps: Vec::new(), opacity: 1.0 }
+    }
+}
+
+// Lines that aren't understood (yet) are skipped
+pub fn parse_mtl(input: &str) -> Vec<Material>{
+    let mut materials: Vec<Material> = Vec::new();
+    for line in input.split('\n'){
+        match parse_mtl_line(line){
+            Ok((_, MtlLine::NewMtl(name))) => materials.push(Material{ name: name.into_owned(), ..Default::default() }),
+            Ok((_, MtlLine::TextureMap{ map, file })) => if let Some(m) = materials.last_mut() { m.texture_maps.push((map.into_owned(), file.into_owned())); },
+            Ok((_, MtlLine::Opacity(opacity))) => if let Some(m) = materials.last_mut() { m.opacity = opacity; },
+            _ => {}
+        }
+    }
+    materials
+}
+
+pub fn parse_mtl_line(input: &str) -> IResult<&str, MtlLine<'_>>{
+    alt((
+        map(end_line, |_| MtlLine::NoData),
+        map(tuple(( space0, tag("newmtl"), space1, parse_name, end_line )), |data| MtlLine::NewMtl(Cow::Borrowed(data.3))),
+        map(tuple(( space0, parse_opacity, end_line )), |(_, opacity, _)| MtlLine::Opacity(opacity)),
+        map(tuple(( space0, parse_texture_map, end_line )), |(_, (name, file), _)| MtlLine::TextureMap{ map: Cow::Borrowed(name), file: Cow::Borrowed(file) }),
+    ))(input)
+}
+
+// For d and Tr, d can have a -halo option which is ignored
+fn parse_opacity(input: &str) -> IResult<&str, f32>{
+    let float = |i| parse_float::<f32>(i, &ParseOptions::default());
+    alt((
+        map(tuple(( tag("d"), space1, opt(tuple(( tag("-halo"), space1 ))), float )), |data| data.3),
+        map(tuple(( tag("Tr"), space1, float )), |data| 1.0 - data.2),
+    ))(input)
+}
+
+// For map_Kd, map_Ks, bump, ... with their options
+fn parse_texture_map(input: &str) -> IResult<&str, (&str, &str)>{
+    let statement = alt(( recognize_map_name, tag("bump"), tag("disp"), tag("decal"), tag("refl") ));
+    let (input, data) = tuple(( statement, many0(preceded(space1, texture_option)), space1, is_not("#\r\n") ))(input)?;
+    Ok((input, (data.0, data.3.trim_end())))
+}
+
+#[inline]
+fn recognize_map_name(input: &str) -> IResult<&str, &str>{
+    recognize(tuple(( tag("map_"), parse_name )))(input)
+}
+
+// A number that is a whole word, so a file called 2.png isn't taken for one
+#[inline]
+fn option_num(input: &str) -> IResult<&str, &str>{ terminated(consume_num, peek(alt((space1, eof))))(input) }
+
+// The options that can come before the file, see the .mtl spec
+fn texture_option(input: &str) -> IResult<&str, ()>{
+    let on_off = || alt((tag("on"), tag("off")));
+    alt((
+        value((), tuple(( alt((tag("-o"), tag("-s"), tag("-t"))), space1, option_num, opt(preceded(space1, option_num)), opt(preceded(space1, option_num)) ))),
+        value((), tuple(( alt((tag("-bm"), tag("-boost"), tag("-texres"))), space1, option_num ))),
+        value((), tuple(( tag("-mm"), space1, option_num, space1, option_num ))),
+        value((), tuple(( alt((tag("-blendu"), tag("-blendv"), tag("-cc"), tag("-clamp"))), space1, on_off() ))),
+        value((), tuple(( tag("-imfchan"), space1, one_of("rgbmlz") ))),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texture_map_options(){
+        let (_, res) = parse_mtl_line("map_Kd -s 2 2 2 brick.png").unwrap();
+        assert_eq!(res, MtlLine::TextureMap{ map: Cow::Borrowed("map_Kd"), file: Cow::Borrowed("brick.png") });
+        let (_, res) = parse_mtl_line("map_Kd -o 1 1 0 -clamp on -bm 0.5 tex.png # comment").unwrap();
+        assert_eq!(res, MtlLine::TextureMap{ map: Cow::Borrowed("map_Kd"), file: Cow::Borrowed("tex.png") });
+        let (_, res) = parse_mtl_line("bump -imfchan l 2.png").unwrap();
+        assert_eq!(res, MtlLine::TextureMap{ map: Cow::Borrowed("bump"), file: Cow::Borrowed("2.png") });
+    }
+
+    #[test]
+    fn test_opacity(){
+        assert_eq!(parse_mtl_line("d 0.5").unwrap().1, MtlLine::Opacity(0.5));
+        assert_eq!(parse_mtl_line("Tr 0.5").unwrap().1, MtlLine::Opacity(0.5));
+        assert_eq!(parse_mtl_line("Tr 0.25 # mostly opaque").unwrap().1, MtlLine::Opacity(0.75));
+        assert_eq!(parse_mtl_line("d -halo 0.8").unwrap().1, MtlLine::Opacity(0.8));
+        let materials = parse_mtl("newmtl glass\nTr 0.9\nnewmtl brick\n");
+        assert!((materials[0].opacity - 0.1).abs() < 1e-6);
+        assert_eq!(materials[1].opacity, 1.0);
+    }
+
+    #[test]
+    fn test_parse_mtl(){
+        let materials = parse_mtl("# two materials\nnewmtl brick\nKd 1 0 0\nmap_Kd -s 2 2 2 brick.png\n\nnewmtl glass\nmap_Ks glass.png\n");
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "brick");
+        assert_eq!(materials[0].texture_maps, vec![(String::from("map_Kd"), String::from("brick.png"))]);
+        assert_eq!(materials[1].texture_maps, vec![(String::from("map_Ks"), String::from("glass.png"))]);
+    }
+}
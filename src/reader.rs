@@ -0,0 +1,208 @@
+use std::{borrow::Cow, io::{self, BufRead}, marker::PhantomData, str::FromStr};
+
+use crate::{LineResult, ObjFloat, ParseOptions, line_result, not_text_error};
+
+// The lines of an in-memory input parsed one at a time, in order, see parse_file_sequential
+// Unlike the iterators parse_file and friends return this has a name, so it can be kept in a struct field or passed around
+#[derive(Debug, Clone)]
+pub struct ObjLines<'a, T, I>{
+    lines: Option<std::str::Split<'a, char>>, // None if the input doesn't look like text
+    not_text: Option<LineResult<'a, T, I>>, // The error for that, until it's been given
+    options: ParseOptions,
+    _marker: PhantomData<fn() -> (T, I)>
+}
+
+impl<'a, T, I> Iterator for ObjLines<'a, T, I>
+where T: FromStr + ObjFloat, I: FromStr{
+    type Item = LineResult<'a, T, I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(lines) = &mut self.lines else {
+            return self.not_text.take();
+        };
+        lines.next().map(|line| line_result(line, &self.options))
+    }
+}
+
+// Same lines as parse_file gives, but on the calling thread, for when the input is small or rayon isn't wanted
+pub fn parse_file_sequential<T, I>(input: &str) -> ObjLines<'_, T, I>{
+    parse_file_sequential_with_options(input, ParseOptions::default())
+}
+
+// Note: Unlike parse_file_with_options skip_empty isn't applied
+pub fn parse_file_sequential_with_options<T, I>(input: &str, options: ParseOptions) -> ObjLines<'_, T, I>{
+    let not_text = not_text_error(input);
+    ObjLines{ lines: not_text.is_none().then(|| input.split('\n')), not_text, options, _marker: PhantomData }
+}
+
+// How many bytes parse_reader_with_progress reads between calls to on_progress
+pub const PROGRESS_INTERVAL: u64 = 64 * 1024;
+
+// The only result to give if what's buffered at the start of r doesn't look like text (see looks_like_text), None if it does
+// Fills r's buffer, so this reads as soon as it's called rather than on the first next
+fn reader_not_text_error<R, T, I>(r: &mut R) -> Option<io::Result<LineResult<'static, T, I>>>
+where R: BufRead{
+    let buf = match r.fill_buf(){
+        Ok(buf) => buf,
+        Err(e) => return Some(Err(e))
+    };
+    let sample = match std::str::from_utf8(buf){
+        Ok(sample) => Cow::Borrowed(sample),
+        // Just a char cut off by the end of the buffer, that's not a reason to call it binary
+        Err(e) if e.error_len().is_none() => Cow::Borrowed(std::str::from_utf8(&buf[..e.valid_up_to()]).unwrap_or_default()),
+        Err(_) => String::from_utf8_lossy(buf)
+    };
+    not_text_error(&sample).map(Ok)
+}
+
+// Parses lines as they are read, so the whole file never has to be in memory, unlike parse_file this is sequential
+// If the start of the input doesn't look like text this gives a single error (or the io error from reading it) instead
+pub fn parse_reader<'r, R, T, I>(mut r: R) -> impl Iterator<Item = io::Result<LineResult<'static, T, I>>> + 'r
+where R: BufRead + 'r, T: FromStr + ObjFloat + 'r, I: FromStr + 'r{
+    let not_text = reader_not_text_error(&mut r);
+    let lines = not_text.is_none().then(|| r.lines().map(|line|{
+        let line = line?;
+        Ok(line_result(&line, &ParseOptions::default()).into_owned())
+    }));
+    not_text.into_iter().chain(lines.into_iter().flatten())
+}
+
+// Same as parse_reader, but calls on_progress with the number of bytes read so far every PROGRESS_INTERVAL bytes,
+// and once more with the total at the end, for progress bars
+// Input that doesn't look like text gives the same single error as parse_reader, and on_progress is never called
+pub fn parse_reader_with_progress<'r, R, T, I, F>(mut r: R, mut on_progress: F) -> impl Iterator<Item = io::Result<LineResult<'static, T, I>>> + 'r
+where R: BufRead + 'r, T: FromStr + ObjFloat + 'r, I: FromStr + 'r, F: FnMut(u64) + 'r{
+    let mut buf = String::new();
+    let (mut consumed, mut reported) = (0u64, 0u64);
+    let mut not_text = reader_not_text_error(&mut r);
+    let mut done = not_text.is_some(); // So the end is only reported once, even if next is called again
+    std::iter::from_fn(move ||{
+        if let Some(res) = not_text.take() { return Some(res); }
+        if done { return None; }
+        buf.clear();
+        match r.read_line(&mut buf){
+            Ok(0) => {
+                if consumed != reported || consumed == 0 { on_progress(consumed); }
+                done = true;
+                None
+            },
+            Ok(n) => {
+                consumed += n as u64;
+                if consumed - reported >= PROGRESS_INTERVAL { reported = consumed; on_progress(consumed); }
+                let line = buf.strip_suffix('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).unwrap_or(&buf); // Like BufRead::lines
+                Some(Ok(line_result(line, &ParseOptions::default()).into_owned()))
+            },
+            Err(e) => Some(Err(e))
+        }
+    })
+}
+
+// Same as parse_reader, for gzip-compressed (.obj.gz) files
+#[cfg(feature = "gzip")]
+pub fn parse_gz_reader<'r, R, T, I>(r: R) -> impl Iterator<Item = io::Result<LineResult<'static, T, I>>> + 'r
+where R: io::Read + 'r, T: FromStr + ObjFloat + 'r, I: FromStr + 'r{
+    parse_reader(io::BufReader::new(flate2::read::GzDecoder::new(r)))
+}
+
+// Maps the file into memory and parses it in parallel like parse_file, without reading it into a String first
+// Safety caveat: the file must not be changed (by this or another process) while it's mapped, the contents could change
+// under the parser otherwise, which is undefined behaviour, so only use this on files nothing else is writing to
+#[cfg(feature = "mmap")]
+pub fn parse_mmap<T, I>(path: &std::path::Path) -> io::Result<Vec<LineResult<'static, T, I>>>
+where T: Send + FromStr + ObjFloat, I: Send + FromStr + 'static{
+    use rayon::iter::ParallelIterator;
+    let file = std::fs::File::open(path)?;
+    // SAFETY: See above, it's up to the caller to make sure the file isn't modified while this runs
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let input = std::str::from_utf8(&mmap).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(crate::parse_file(input).map(LineResult::into_owned).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Face, VertexData};
+    use rayon::iter::ParallelIterator;
+
+    #[test]
+    fn test_parse_reader(){
+        let res: Vec<LineResult<f32, u32>> = parse_reader("v 1 2 3\r\ng side\nf 1 1 1\n".as_bytes()).collect::<io::Result<_>>().unwrap();
+        assert_eq!(res.len(), 3);
+        assert!(matches!(&res[0], LineResult::VertDataLine(VertexData::Coord3{..})));
+        assert!(matches!(&res[1], LineResult::Group(name) if name == "side"));
+        assert!(matches!(&res[2], LineResult::FaceLine(Face::Face3{..})));
+    }
+
+    #[test]
+    fn test_parse_reader_binary(){
+        let not_text = |res: Vec<io::Result<LineResult<f32, u32>>>| res.len() == 1 && matches!(&res[0], Ok(LineResult::Error(e)) if e.contains("does not look like a text OBJ"));
+        let input = "\u{1}\n".repeat(1000);
+        assert!(not_text(parse_reader(input.as_bytes()).collect()));
+        let mut calls = 0;
+        assert!(not_text(parse_reader_with_progress(input.as_bytes(), |_| calls += 1).collect()));
+        assert_eq!(calls, 0);
+        let bytes: Vec<u8> = (0..=255u8).cycle().take(10_000).collect(); // Not even utf8
+        assert!(not_text(parse_reader(bytes.as_slice()).collect()));
+        let res: Vec<LineResult<f32, u32>> = parse_file_sequential(&input).collect();
+        assert!(res.len() == 1 && matches!(&res[0], LineResult::Error(e) if e.contains("does not look like a text OBJ")));
+
+        // A multi-byte char cut off by the end of the reader's buffer is still text
+        let input = "v 1 2 3 # caf\u{e9}\n".repeat(100);
+        let cut = input.find('\u{e9}').unwrap() + 1;
+        let res: Vec<LineResult<f32, u32>> = parse_reader(io::BufReader::with_capacity(cut, input.as_bytes())).collect::<io::Result<_>>().unwrap();
+        assert_eq!(res.len(), 100);
+    }
+
+    #[test]
+    fn test_obj_lines(){
+        struct Loader<'a>{ lines: ObjLines<'a, f32, u32>, faces: usize }
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 3 2 1";
+        let mut loader = Loader{ lines: parse_file_sequential(input), faces: 0 };
+        for line in loader.lines.by_ref().take(4){
+            if let LineResult::FaceLine(_) = line { loader.faces += 1; }
+        }
+        assert_eq!(loader.faces, 1);
+        assert!(matches!(loader.lines.next(), Some(LineResult::FaceLine(Face::Face3{..}))));
+        assert!(loader.lines.next().is_none());
+        let kinds = |lines: Vec<LineResult<f32, u32>>| lines.iter().map(LineResult::kind).collect::<Vec<_>>();
+        let input = "v 1 2 3\n# a cube\ng side\nwhat\nf 1 1 1\n";
+        assert_eq!(kinds(parse_file_sequential(input).collect()), kinds(crate::parse_file(input).collect()));
+    }
+
+    #[test]
+    fn test_parse_reader_with_progress(){
+        let input = "v 1 2 3\r\nf 1 1 1\n".repeat(20_000);
+        let mut calls = Vec::new();
+        let res: Vec<LineResult<f32, u32>> = parse_reader_with_progress(input.as_bytes(), |n| calls.push(n)).collect::<io::Result<_>>().unwrap();
+        assert_eq!(res.len(), 40_000);
+        assert!(matches!(&res[0], LineResult::VertDataLine(VertexData::Coord3{..})));
+        assert!(calls.len() > 2);
+        assert!(calls.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(*calls.last().unwrap(), input.len() as u64);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_parse_mmap(){
+        let path = std::env::temp_dir().join(format!("objld_test_parse_mmap_{}.obj", std::process::id()));
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let res: io::Result<Vec<LineResult<f32, u32>>> = parse_mmap(&path);
+        std::fs::remove_file(&path).unwrap();
+        let res = res.unwrap();
+        assert_eq!(res.len(), 5);
+        assert!(matches!(&res[3], LineResult::FaceLine(Face::Face3{..})));
+        assert!(parse_mmap::<f32, u32>(&path).is_err()); // Gone now
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_parse_gz_reader(){
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+        let res: Vec<LineResult<f32, u32>> = parse_gz_reader(compressed.as_slice()).collect::<io::Result<_>>().unwrap();
+        assert_eq!(res.len(), 4);
+        assert!(matches!(&res[3], LineResult::FaceLine(Face::Face3{..})));
+    }
+}
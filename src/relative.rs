@@ -0,0 +1,65 @@
+use crate::{LineResult, VertexData, resolve_index};
+
+// Rewrites negative (relative) face indices into positive (absolute) ones while going through the results,
+// by counting the positions, tex coords and normals seen so far, so the faces don't depend on where they are anymore
+// Indices that point before the start of the file are left as they are, they don't resolve either way
+pub fn resolve_relative<'a, T, I>(results: impl IntoIterator<Item = LineResult<'a, T, I>>) -> impl Iterator<Item = LineResult<'a, T, I>>
+where I: Copy + TryInto<i64> + TryFrom<i64>{
+    let mut counts = [0usize; 3]; // Positions, tex coords and normals
+    results.into_iter().map(move |mut line|{
+        match &mut line{
+            LineResult::VertDataLine(v) => match v{
+                VertexData::Coord2{ .. } | VertexData::Coord3{ .. } | VertexData::Coord4{ .. } => counts[0] += 1,
+                VertexData::TextureCoord1{ .. } | VertexData::TextureCoord2{ .. } | VertexData::TextureCoord3{ .. } => counts[1] += 1,
+                VertexData::Normal{ .. } => counts[2] += 1,
+            },
+            LineResult::FaceLine(face) => for v in face.vertices_mut(){
+                v.coord_rindex = absolute_index(v.coord_rindex, counts[0]);
+                v.texcoord_rindex = v.texcoord_rindex.map(|i| absolute_index(i, counts[1]));
+                v.normal_rindex = v.normal_rindex.map(|i| absolute_index(i, counts[2]));
+            },
+            _ => {}
+        }
+        line
+    })
+}
+
+#[inline]
+pub(crate) fn absolute_index<I>(rindex: I, count: usize) -> I
+where I: Copy + TryInto<i64> + TryFrom<i64>{
+    match rindex.try_into(){
+        Ok(i) if i < 0 => resolve_index(i, count).and_then(|i| i64::try_from(i + 1).ok()).and_then(|i| I::try_from(i).ok()).unwrap_or(rindex),
+        _ => rindex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Face, parse_file};
+    use rayon::iter::ParallelIterator;
+
+    #[test]
+    fn test_resolve_relative(){
+        let input = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -1 -2 -3\nv 0 0 1\nvn 0 0 1\nf -1//-1 -2//1 2//-1\nf -9 1 2\n";
+        let faces: Vec<Face<i32>> = resolve_relative(parse_file::<f32, i32>(input).collect::<Vec<_>>()).filter_map(|line| match line{
+            LineResult::FaceLine(face) => Some(face),
+            _ => None
+        }).collect();
+        let indices: Vec<Vec<_>> = faces.iter().map(|f| f.vertices().map(|v| (v.coord_rindex, v.normal_rindex)).collect()).collect();
+        assert_eq!(indices, vec![
+            vec![(3, None), (2, None), (1, None)],
+            vec![(4, Some(1)), (3, Some(1)), (2, Some(1))],
+            vec![(-9, None), (1, None), (2, None)],
+        ]);
+    }
+
+    #[test]
+    fn test_absolute_index_large(){
+        assert_eq!(absolute_index(-1i64, usize::MAX), -1); // The absolute index is too large for an i64, so it stays relative
+        assert_eq!(absolute_index(-1i64, i64::MAX as usize), i64::MAX);
+        assert_eq!(absolute_index(i64::MIN, 1 << 63), 1);
+        assert_eq!(absolute_index(-3i32, 2), -3);
+        assert_eq!(absolute_index(-1i8, 200), -1); // 200 doesn't fit in an i8
+    }
+}
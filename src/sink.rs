@@ -0,0 +1,60 @@
+use crate::{Face, LineResult, VertexData};
+
+// Lets the results be streamed into any mesh type instead of going through Mesh
+pub trait MeshSink<T, I>{
+    // z is None for 2d coords
+    fn add_position(&mut self, x: T, y: T, z: Option<T>);
+    fn add_normal(&mut self, x: T, y: T, z: T);
+    // v and w are None if the texcoord doesn't have them
+    fn add_texcoord(&mut self, u: T, v: Option<T>, w: Option<T>);
+    fn add_face(&mut self, face: Face<I>);
+}
+
+// Feeds every element in the results to the sink, in order, everything else (context, errors, ...) is skipped
+pub fn drive<'a, T, I, S>(results: impl IntoIterator<Item = LineResult<'a, T, I>>, sink: &mut S)
+where S: MeshSink<T, I>{
+    for line in results{
+        match line{
+            LineResult::VertDataLine(v) => match v{
+                VertexData::Coord2{ x, y } => sink.add_position(x, y, None),
+                VertexData::Coord3{ x, y, z } => sink.add_position(x, y, Some(z)),
+                VertexData::Coord4{ x, y, z, w: _ } => sink.add_position(x, y, Some(z)), // See VertexData::homogenize for using w
+                VertexData::Normal{ x, y, z } => sink.add_normal(x, y, z),
+                VertexData::TextureCoord3{ u, v, w } => sink.add_texcoord(u, Some(v), Some(w)),
+                VertexData::TextureCoord2{ u, v } => sink.add_texcoord(u, Some(v), None),
+                VertexData::TextureCoord1{ u } => sink.add_texcoord(u, None, None),
+            },
+            LineResult::FaceLine(f) => sink.add_face(f),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_file;
+    use rayon::iter::ParallelIterator;
+
+    #[derive(Default)]
+    struct CountingSink{
+        positions: usize,
+        normals: usize,
+        texcoords: usize,
+        faces: usize
+    }
+
+    impl MeshSink<f32, i32> for CountingSink{
+        fn add_position(&mut self, _x: f32, _y: f32, _z: Option<f32>){ self.positions += 1; }
+        fn add_normal(&mut self, _x: f32, _y: f32, _z: f32){ self.normals += 1; }
+        fn add_texcoord(&mut self, _u: f32, _v: Option<f32>, _w: Option<f32>){ self.texcoords += 1; }
+        fn add_face(&mut self, _face: Face<i32>){ self.faces += 1; }
+    }
+
+    #[test]
+    fn test_drive(){
+        let mut sink = CountingSink::default();
+        drive(parse_file("v 0 0 0\nv 1 0\nvn 0 0 1\nvt 0.5\nvt 0 1\no thing\nf 1 2 1\nf 1 2 1 2\nbad line\n").collect::<Vec<_>>(), &mut sink);
+        assert_eq!((sink.positions, sink.normals, sink.texcoords, sink.faces), (2, 1, 2, 2));
+    }
+}
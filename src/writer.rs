@@ -0,0 +1,296 @@
+use std::{fmt::Display, io::{self, Write}, str::FromStr};
+
+use rayon::{iter::{Either, ParallelIterator}, str::ParallelString};
+
+use crate::{CurveType, Face, FreeForm, LineResult, MaterialRef, ObjFloat, ParseOptions, TextureMapRef, VertexData, VertexIndeces, line_result, not_text_error};
+
+// Writes a single line (without the newline) in the usual .obj format, Error results can't be written and fail with InvalidInput
+pub fn write_line<W, T, I>(w: &mut W, line: &LineResult<'_, T, I>) -> io::Result<()>
+where W: Write, T: Display, I: Display{
+    match line{
+        LineResult::VertDataLine(v) => match v{
+            VertexData::Coord2{ x, y } => write!(w, "v {} {}", x, y),
+            VertexData::Coord3{ x, y, z } => write!(w, "v {} {} {}", x, y, z),
+            VertexData::Coord4{ x, y, z, w: weight } => write!(w, "v {} {} {} {}", x, y, z, weight),
+            VertexData::Normal{ x, y, z } => write!(w, "vn {} {} {}", x, y, z),
+            VertexData::TextureCoord3{ u, v, w: tex_w } => write!(w, "vt {} {} {}", u, v, tex_w),
+            VertexData::TextureCoord2{ u, v } => write!(w, "vt {} {}", u, v),
+            VertexData::TextureCoord1{ u } => write!(w, "vt {}", u),
+        },
+        LineResult::FaceLine(f) => write_face(w, f),
+        LineResult::Group(name) => write!(w, "g {}", name),
+        LineResult::Object(name) => write!(w, "o {}", name),
+        LineResult::Material(MaterialRef::UseMtl(Some(name))) => write!(w, "usemtl {}", name),
+        LineResult::Material(MaterialRef::UseMtl(None)) => write!(w, "usemtl"),
+        LineResult::Material(MaterialRef::MtlLib(names)) => write!(w, "mtllib {}", names.join(" ")),
+        LineResult::TextureMap(TextureMapRef::UseMap(name)) => write!(w, "usemap {}", name),
+        LineResult::TextureMap(TextureMapRef::MapLib(names)) => write!(w, "maplib {}", names.join(" ")),
+        LineResult::FreeFormLine(f) => match f{
+            FreeForm::CsType{ rational, kind } => {
+                let kind = match kind{
+                    CurveType::BMatrix => "bmatrix",
+                    CurveType::Bezier => "bezier",
+                    CurveType::BSpline => "bspline",
+                    CurveType::Cardinal => "cardinal",
+                    CurveType::Taylor => "taylor",
+                };
+                write!(w, "cstype {}{}", if *rational { "rat " } else { "" }, kind)
+            },
+            FreeForm::Degree{ u, v: Some(v) } => write!(w, "deg {} {}", u, v),
+            FreeForm::Degree{ u, v: None } => write!(w, "deg {}", u),
+            FreeForm::Curve{ u0, u1, control_points } => {
+                write!(w, "curv {} {}", u0, u1)?;
+                control_points.iter().try_for_each(|i| write!(w, " {}", i))
+            },
+        },
+        LineResult::NoData => Ok(()),
+        LineResult::Error(e) => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("can't write an error line ({})", e)))
+    }
+}
+
+fn write_face<W, I>(w: &mut W, face: &Face<I>) -> io::Result<()>
+where W: Write, I: Display{
+    write!(w, "f")?;
+    face.vertices().try_for_each(|v| { write!(w, " ")?; write_face_vertex(w, v) })
+}
+
+fn write_face_vertex<W, I>(w: &mut W, v: &VertexIndeces<I>) -> io::Result<()>
+where W: Write, I: Display{
+    match (&v.texcoord_rindex, &v.normal_rindex){
+        (None, None) => write!(w, "{}", v.coord_rindex),
+        (Some(t), None) => write!(w, "{}/{}", v.coord_rindex, t),
+        (None, Some(n)) => write!(w, "{}//{}", v.coord_rindex, n),
+        (Some(t), Some(n)) => write!(w, "{}/{}/{}", v.coord_rindex, t, n),
+    }
+}
+
+// Writes .obj lines one element at a time, for generating files without building the LineResults first
+// Keeps count of what it wrote so every method returns the (1-based) index of its element and write_face can check its indices
+pub struct ObjWriter<W: Write>{
+    w: W,
+    positions: usize,
+    texcoords: usize,
+    normals: usize,
+    faces: usize
+}
+
+impl<W: Write> ObjWriter<W>{
+    pub fn new(w: W) -> Self{ Self{ w, positions: 0, texcoords: 0, normals: 0, faces: 0 } }
+
+    pub fn write_vertex<T: Display>(&mut self, x: T, y: T, z: T) -> io::Result<usize>{
+        writeln!(self.w, "v {} {} {}", x, y, z)?;
+        self.positions += 1;
+        Ok(self.positions)
+    }
+
+    pub fn write_normal<T: Display>(&mut self, x: T, y: T, z: T) -> io::Result<usize>{
+        writeln!(self.w, "vn {} {} {}", x, y, z)?;
+        self.normals += 1;
+        Ok(self.normals)
+    }
+
+    pub fn write_texcoord<T: Display>(&mut self, u: T, v: T) -> io::Result<usize>{
+        writeln!(self.w, "vt {} {}", u, v)?;
+        self.texcoords += 1;
+        Ok(self.texcoords)
+    }
+
+    // Fails with InvalidInput (writing nothing) if an index doesn't refer to an element that was already written
+    pub fn write_face<I>(&mut self, face: &Face<I>) -> io::Result<usize>
+    where I: Display + Copy + TryInto<i64>{
+        if face.vertices().any(|v| v.resolve(self.positions, self.texcoords, self.normals).is_none()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "face refers to an element that wasn't written"));
+        }
+        write_face(&mut self.w, face)?;
+        self.w.write_all(b"\n")?;
+        self.faces += 1;
+        Ok(self.faces)
+    }
+
+    pub fn into_inner(self) -> W{ self.w }
+}
+
+// A number that keeps the text it was parsed from, for writing files back out without reformatting every number (+1.000 stays
+// +1.000 instead of becoming 1), parse with it as T (parse_file::<Exact<f32>, i32>) and write_line gives back the original text
+// The text is owned since FromStr can't borrow from the input, with ParseOptions::comma_decimal it has '.' in place of the ','
+#[derive(Debug, Clone)]
+pub struct Exact<T>{
+    pub value: T,
+    pub text: String
+}
+
+impl<T: FromStr> FromStr for Exact<T>{
+    type Err = T::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Exact{ value: T::from_str(s)?, text: s.to_string() })
+    }
+}
+
+impl<T> Display for Exact<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+// Only the values are compared, so 1.0 and 1.000 are equal
+impl<T: PartialEq> PartialEq for Exact<T>{
+    fn eq(&self, other: &Self) -> bool{ self.value == other.value }
+}
+
+// Wrapping a tex coord replaces the text with the new value's, but only if it changed
+impl<T> ObjFloat for Exact<T>
+where T: ObjFloat + Clone + PartialEq + Display{
+    fn from_num(num: &str) -> Option<Self>{ Some(Exact{ value: T::from_num(num)?, text: num.to_string() }) }
+    fn is_infinite(&self) -> bool{ self.value.is_infinite() }
+    fn clamp_unit(self) -> Self{ self.rewrap(T::clamp_unit) }
+    fn repeat_unit(self) -> Self{ self.rewrap(T::repeat_unit) }
+}
+
+impl<T> Exact<T>
+where T: Clone + PartialEq + Display{
+    fn rewrap(self, f: impl FnOnce(T) -> T) -> Self{
+        let value = f(self.value.clone());
+        if value == self.value { self } else { Exact{ text: value.to_string(), value } }
+    }
+}
+
+// So a Mesh can be built out of them
+impl<T: Default + Display> Default for Exact<T>{
+    fn default() -> Self {
+        let value = T::default();
+        Exact{ text: value.to_string(), value }
+    }
+}
+
+// A parsed line that remembers its original text, so it can be written back out byte for byte unless it was changed
+#[derive(Debug)]
+pub struct PassthroughLine<'a, T, I>{
+    source: Option<&'a str>,
+    crlf: bool,
+    result: LineResult<'a, T, I>
+}
+
+impl<'a, T, I> PassthroughLine<'a, T, I>{
+    pub fn result(&self) -> &LineResult<'a, T, I>{ &self.result }
+
+    // Marks the line as edited, so it will be written with write_line instead of as it was
+    pub fn result_mut(&mut self) -> &mut LineResult<'a, T, I>{
+        self.source = None;
+        &mut self.result
+    }
+
+    pub fn is_edited(&self) -> bool{ self.source.is_none() }
+}
+
+// Same as parse_file, but every line keeps its original text (see write_passthrough)
+// Input that doesn't look like text is a single line with parse_file's error, its text is the whole input so it's still written back as it was
+pub fn parse_file_passthrough<'input, T, I>(input: &'input str) -> impl ParallelIterator<Item = PassthroughLine<'input, T, I>> + 'input
+where T: Send + FromStr + ObjFloat + 'input, I: Send + FromStr + 'input{
+    if let Some(error) = not_text_error(input) { return Either::Right(rayon::iter::once(PassthroughLine{ source: Some(input), crlf: false, result: error })); }
+    Either::Left(
+        input.par_split('\n')
+        .map(|line| PassthroughLine{
+            source: Some(line),
+            crlf: line.ends_with('\r'),
+            result: line_result(line, &ParseOptions::default())
+        })
+    )
+}
+
+// Writes the lines back out, untouched ones exactly as they were in the input and edited ones with write_line (keeping their line ending)
+pub fn write_passthrough<W, T, I>(w: &mut W, lines: &[PassthroughLine<'_, T, I>]) -> io::Result<()>
+where W: Write, T: Display, I: Display{
+    for (n, line) in lines.iter().enumerate(){
+        if n != 0 { w.write_all(b"\n")?; }
+        match line.source{
+            Some(source) => w.write_all(source.as_bytes())?,
+            None => {
+                write_line(w, &line.result)?;
+                if line.crlf { w.write_all(b"\r")?; }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_line(){
+        let input = "v 1 2 3\nvt 0.5\nvn 0 -1 0\nf 1 2/1 3//1 4/1/1\ng side\nusemtl brick\nmtllib a.mtl b.mtl\nusemap brick\nmaplib textures.map\ncstype rat bezier\ndeg 3\ncurv 0 1 1 2 3\n";
+        let lines: Vec<LineResult<f32, i32>> = crate::parse_file(input).collect();
+        let mut out = Vec::new();
+        for line in &lines{
+            write_line(&mut out, line).unwrap();
+            out.push(b'\n');
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), input.to_string() + "\n");
+        assert!(write_line(&mut Vec::new(), &LineResult::<f32, i32>::Error(String::from("bad"))).is_err());
+    }
+
+    #[test]
+    fn test_obj_writer(){
+        let mut writer = ObjWriter::new(Vec::new());
+        let v: Vec<i32> = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]].iter().map(|&[x, y, z]| writer.write_vertex(x, y, z).unwrap() as i32).collect();
+        let n = writer.write_normal(0.0, 0.0, 1.0).unwrap() as i32;
+        let face = Face::Face3{ v1: VertexIndeces{ coord_rindex: v[0], texcoord_rindex: None, normal_rindex: Some(n) }, v2: VertexIndeces{ coord_rindex: v[1], texcoord_rindex: None, normal_rindex: Some(n) }, v3: VertexIndeces{ coord_rindex: -1, texcoord_rindex: None, normal_rindex: Some(-1) } };
+        assert_eq!(writer.write_face(&face).unwrap(), 1);
+        assert!(writer.write_face(&face.clone().map_indices(|i| i + 5)).is_err());
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(out, "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 -1//-1\n");
+        let mesh: crate::Mesh<f32, i32> = crate::collect_mesh(crate::parse_file(&out).collect::<Vec<_>>());
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.faces, vec![face.map_indices(|i| if i == -1 { 3 } else { i }).map_vertices(|v| VertexIndeces{ normal_rindex: Some(1), ..v })]); // Made absolute
+    }
+
+    #[test]
+    fn test_exact_roundtrip(){
+        let input = "v +1.000 2.5 -0.0\nvt 1e-3 .5\nvn 0.0 0 1.\nv 1e999 0 0\n";
+        let lines: Vec<LineResult<Exact<f32>, i32>> = crate::parse_file(input).collect();
+        let LineResult::VertDataLine(VertexData::Coord3{ x, y, z }) = &lines[0] else { panic!("Wrong line type!"); };
+        assert_eq!((x.value, y.value, z.value), (1.0, 2.5, 0.0));
+        assert!(z.value.is_sign_negative());
+        assert!(matches!(lines[1], LineResult::Error(_))); // .5 isn't a number as far as OBJ is concerned
+        assert!(matches!(lines[3], LineResult::Error(_))); // Overflow is still caught
+        let mut out = Vec::new();
+        write_line(&mut out, &lines[0]).unwrap();
+        assert_eq!(out, b"v +1.000 2.5 -0.0");
+        let mut out = Vec::new();
+        write_line(&mut out, &lines[2]).unwrap();
+        assert_eq!(out, b"vn 0.0 0 1.");
+        let mesh: crate::Mesh<Exact<f32>, i32> = crate::collect_mesh(lines.into_iter().take(1).collect::<Vec<_>>());
+        assert_eq!(mesh.positions[0].each_ref().map(|c| c.text.as_str()), ["+1.000", "2.5", "-0.0"]);
+
+        // Tex coord wrapping applies to them too, only the ones it changes get new text
+        let clamp = ParseOptions{ texcoord_wrap: crate::TexcoordWrap::Clamp, ..Default::default() };
+        let (_, line): (_, LineResult<Exact<f32>, i32>) = crate::parse_line_with_options("vt -0.5 0.250", &clamp).unwrap();
+        let LineResult::VertDataLine(VertexData::TextureCoord2{ u, v }) = line else { panic!("Wrong line type!"); };
+        assert_eq!((u.value, u.text.as_str(), v.value, v.text.as_str()), (0.0, "0", 0.25, "0.250"));
+    }
+
+    #[test]
+    fn test_passthrough_roundtrip(){
+        let input = "# made by hand\r\nv  1.000   2.0 3 # first\r\nv 4 5 6\r\n\r\n  f 1/1 2/1 1/1   \r\ngarbage here\r\n";
+        let mut lines: Vec<PassthroughLine<f32, i32>> = parse_file_passthrough(input).collect();
+        let mut out = Vec::new();
+        write_passthrough(&mut out, &lines).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), input);
+
+        *lines[2].result_mut() = LineResult::VertDataLine(VertexData::Coord3{ x: 7.0, y: 8.0, z: 9.0 });
+        assert!(lines[2].is_edited() && !lines[1].is_edited());
+        let mut out = Vec::new();
+        write_passthrough(&mut out, &lines).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), input.replace("v 4 5 6", "v 7 8 9"));
+
+        // Binary input gets parse_file's single error, but is still written back untouched
+        let input = "v 1 2 3\n\0\0\0\x01\x02garbage\n";
+        let lines: Vec<PassthroughLine<f32, i32>> = parse_file_passthrough(input).collect();
+        assert_eq!(lines.len(), 1);
+        assert!(matches!(lines[0].result(), LineResult::Error(e) if e.contains("does not look like a text OBJ")));
+        let mut out = Vec::new();
+        write_passthrough(&mut out, &lines).unwrap();
+        assert_eq!(out, input.as_bytes());
+    }
+}